@@ -0,0 +1,1048 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use tinytemplate::TinyTemplate;
+
+use super::game::RoundType;
+use super::tribute::{self, Stat, Status, Tribute};
+
+/// A single templated action, e.g. `{0.name} grabs a shovel.`
+///
+/// `killer`, `killed` and `alliance` index into the `tributes` participants.
+/// Participants listed in `alliance` become allies; a `betray` action breaks
+/// every alliance between its participants. `requires`, `consumes` and
+/// `gives` map a participant index to item tags, e.g. `{"0": ["bow"]}`, and
+/// `applies` and `cures` map one to statuses, e.g. `{"1": ["wounded"]}`.
+///
+/// Stats decide who fills the winning and losing sides: `winner` names the
+/// winning participant, otherwise the killers win and the killed lose. `stat`
+/// picks the stat that decides it.
+///
+/// `weight` makes an action more or less likely than its neighbours. A
+/// `once_per_game` action is used at most once, and one with a `cooldown`
+/// sits out the rest of its round plus that many played rounds. `when` limits
+/// the action to some game states, see `Conditions`.
+///
+/// `msgs` holds translations of `msg` keyed by locale code, e.g. `{"de": ...}`.
+/// An `id` lets later packs replace or disable the action.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Action {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// file name of the pack the action was loaded from
+    #[serde(skip)]
+    pub pack: String,
+    pub msg: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub msgs: BTreeMap<String, String>,
+    pub tributes: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub killer: Vec<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub killed: Vec<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alliance: Vec<usize>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub betray: bool,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub requires: BTreeMap<usize, Vec<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub consumes: BTreeMap<usize, Vec<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub gives: BTreeMap<usize, Vec<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub applies: BTreeMap<usize, Vec<Status>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub cures: BTreeMap<usize, Vec<Status>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub winner: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stat: Option<Stat>,
+    #[serde(default = "default_weight", skip_serializing_if = "is_default_weight")]
+    pub weight: f64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub once_per_game: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown: Option<u32>,
+    #[serde(default, skip_serializing_if = "Conditions::is_empty")]
+    pub when: Conditions,
+}
+
+/// Game state an action needs before it can be chosen, e.g.
+/// `{"min_day": 3, "final": 2}` or `{"status": {"0": ["wounded"]}}`
+///
+/// `final` means exactly that many tributes are left; `min_kills` and `status`
+/// apply to the participant with that index.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Conditions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_day: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_day: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_alive: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_alive: Option<usize>,
+    #[serde(rename = "final", skip_serializing_if = "Option::is_none")]
+    pub final_n: Option<usize>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub min_kills: BTreeMap<usize, i32>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub status: BTreeMap<usize, Vec<Status>>,
+}
+
+impl Conditions {
+    pub fn is_empty(&self) -> bool {
+        *self == Conditions::default()
+    }
+    /// Checks the day and alive count; participant conditions are checked
+    /// while picking who takes part
+    pub fn allow(&self, day: i32, n_alive: usize) -> bool {
+        self.min_day.is_none_or(|d| day >= d) &&
+            self.max_day.is_none_or(|d| day <= d) &&
+            self.min_alive.is_none_or(|n| n_alive >= n) &&
+            self.max_alive.is_none_or(|n| n_alive <= n) &&
+            self.final_n.is_none_or(|n| n_alive == n)
+    }
+    /// Checks the participant conditions for slot `slot`
+    pub fn allow_member(&self, slot: usize, tb: &Tribute) -> bool {
+        self.min_kills.get(&slot).is_none_or(|&k| tb.killcount >= k) &&
+            self.status.get(&slot).is_none_or(|statuses| statuses.iter().all(|&s| tb.has_status(s)))
+    }
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+fn is_default_weight(weight: &f64) -> bool {
+    *weight == 1.0
+}
+
+impl Action {
+    /// Items participant `slot` must carry: everything it requires or
+    /// consumes, a tag listed in both counting once
+    pub fn needs(&self, slot: usize) -> Vec<&str> {
+        let mut needs: Vec<&str> = self.requires.get(&slot).into_iter().flatten().map(String::as_str).collect();
+        let consumed: Vec<&str> = self.consumes.get(&slot).into_iter().flatten().map(String::as_str).collect();
+        for &tag in consumed.iter() {
+            let wanted = consumed.iter().filter(|&&t| t == tag).count();
+            if needs.iter().filter(|&&t| t == tag).count() < wanted {
+                needs.push(tag);
+            }
+        }
+        needs
+    }
+    /// Whether participant `slot` is on the winning side
+    pub fn wins(&self, slot: usize) -> bool {
+        match self.winner {
+            Some(winner) => winner == slot,
+            None => self.killer.contains(&slot),
+        }
+    }
+    /// Whether participant `slot` is on the losing side
+    pub fn loses(&self, slot: usize) -> bool {
+        match self.winner {
+            Some(winner) => winner != slot,
+            None => self.killed.contains(&slot),
+        }
+    }
+}
+
+/// The actions available in one kind of round
+///
+/// `weight` only matters for arena events, which are drawn by it, and `id`
+/// lets later packs replace or disable an arena event.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RoundEvents {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub title: String,
+    /// translations of `title` keyed by locale code
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub titles: BTreeMap<String, String>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub color: String,
+    #[serde(default = "default_weight", skip_serializing_if = "is_default_weight")]
+    pub weight: f64,
+    pub nonfatal: Vec<Action>,
+    pub fatal: Vec<Action>,
+}
+
+/// Names of the built-in rounds, which custom rounds may not reuse
+pub const BUILTIN_ROUNDS: &[&str] = &["bloodbath", "feast", "arena", "day", "night", "fallen"];
+
+/// When a custom round is played
+///
+/// After every round named `after`, built-in or custom, the custom round
+/// follows with probability `chance`, as long as the day is within
+/// `min_day`/`max_day` and it has been played fewer than `max_per_game`
+/// times. Rounds following a night belong to the next day.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Schedule {
+    pub after: String,
+    #[serde(default = "default_weight", skip_serializing_if = "is_default_weight")]
+    pub chance: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_day: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_day: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_per_game: Option<u32>,
+    /// added to the fatality factor, like `rules.pacing.fatality_bonus`
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub fatality_bonus: i32,
+}
+
+fn is_zero(n: &i32) -> bool {
+    *n == 0
+}
+
+/// A round type declared by the event pack, e.g. a "storm" after some days
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CustomRound {
+    pub name: String,
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    #[serde(flatten)]
+    pub events: RoundEvents,
+}
+
+/// A complete event pack, as read from `events.json`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EventSet {
+    pub bloodbath: RoundEvents,
+    pub day: RoundEvents,
+    pub night: RoundEvents,
+    pub feast: RoundEvents,
+    #[serde(default)]
+    pub arena: Vec<RoundEvents>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom: Vec<CustomRound>,
+}
+
+/// A problem found while loading an event pack
+///
+/// `location` points at the offending entry, e.g. `day.fatal[12]`.
+#[derive(Clone, Debug)]
+pub struct EventError {
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.location.is_empty() {
+            write!(f, "{}", self.message)
+        }
+        else {
+            write!(f, "{}: {}", self.location, self.message)
+        }
+    }
+}
+
+impl EventError {
+    fn new(location: &str, message: String) -> EventError {
+        EventError{location:location.to_string(), message}
+    }
+}
+
+// round header with the actions left untyped, so each action can be
+// checked on its own and reported with its index
+#[derive(Deserialize)]
+struct RawRound {
+    #[serde(default)]
+    id: Option<String>,
+    title: String,
+    #[serde(default)]
+    titles: BTreeMap<String, String>,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    color: String,
+    #[serde(default = "default_weight")]
+    weight: f64,
+    nonfatal: Vec<Value>,
+    fatal: Vec<Value>,
+}
+
+/// Renders a template against stand-in values, so a malformed template or an
+/// unknown placeholder is reported when the pack loads rather than mid-game
+fn check_template(location: &str, field: &str, template: &str, context: &Value, errors: &mut Vec<EventError>) -> bool {
+    let mut tt = TinyTemplate::new();
+    match tt.add_template("check", template).and_then(|_| tt.render("check", context)) {
+        Ok(_) => true,
+        Err(e) => {
+            errors.push(EventError::new(location, format!("`{}` is not a valid template: {}", field, e)));
+            false
+        }
+    }
+}
+
+/// A template context with `n` tributes, every field filled in
+fn tributes_context(n: usize) -> Value {
+    let fields: Map<String, Value> = tribute::TEMPLATE_FIELDS.iter()
+        .map(|f| (f.to_string(), Value::String(f.to_string())))
+        .collect();
+    Value::Object((0..n).map(|i| (i.to_string(), Value::Object(fields.clone()))).collect())
+}
+
+fn parse_action(location: &str, v: Value, errors: &mut Vec<EventError>) -> Option<Action> {
+    let action: Action = match serde_json::from_value(v) {
+        Ok(action) => action,
+        Err(e) => {
+            errors.push(EventError::new(location, e.to_string()));
+            return None;
+        }
+    };
+    let mut ok = true;
+    if action.id.as_ref().is_some_and(|id| id.trim().is_empty()) {
+        errors.push(EventError::new(location, String::from("`id` is empty")));
+        ok = false;
+    }
+    if action.tributes == 0 {
+        errors.push(EventError::new(location, String::from("`tributes` must be at least 1")));
+        ok = false;
+    }
+    if action.alliance.len() == 1 {
+        errors.push(EventError::new(location, String::from("`alliance` needs at least 2 tributes")));
+        ok = false;
+    }
+    for (field, indices) in [("killer", &action.killer), ("killed", &action.killed), ("alliance", &action.alliance)].iter() {
+        for idx in indices.iter() {
+            if *idx >= action.tributes {
+                errors.push(EventError::new(location, format!(
+                    "`{}` index {} is out of range for {} tributes", field, idx, action.tributes)));
+                ok = false;
+            }
+        }
+    }
+    for (field, items) in [("requires", &action.requires), ("consumes", &action.consumes), ("gives", &action.gives)].iter() {
+        for (idx, tags) in items.iter() {
+            if *idx >= action.tributes {
+                errors.push(EventError::new(location, format!(
+                    "`{}` index {} is out of range for {} tributes", field, idx, action.tributes)));
+                ok = false;
+            }
+            if tags.iter().any(|tag| tag.trim().is_empty()) {
+                errors.push(EventError::new(location, format!("`{}` contains an empty item tag", field)));
+                ok = false;
+            }
+        }
+    }
+    if !(action.weight > 0.0 && action.weight.is_finite()) {
+        errors.push(EventError::new(location, format!("`weight` must be a positive number, found {}", action.weight)));
+        ok = false;
+    }
+    if let Some(winner) = action.winner {
+        if winner >= action.tributes {
+            errors.push(EventError::new(location, format!(
+                "`winner` index {} is out of range for {} tributes", winner, action.tributes)));
+            ok = false;
+        }
+    }
+    let when = &action.when;
+    for (field, min, max) in [("day", when.min_day.map(|d| d as i64), when.max_day.map(|d| d as i64)),
+            ("alive", when.min_alive.map(|n| n as i64), when.max_alive.map(|n| n as i64))].iter() {
+        if let (Some(min), Some(max)) = (min, max) {
+            if min > max {
+                errors.push(EventError::new(location, format!(
+                    "`when.min_{}` {} is above `when.max_{}` {}", field, min, field, max)));
+                ok = false;
+            }
+        }
+    }
+    for idx in when.min_kills.keys().chain(when.status.keys()) {
+        if *idx >= action.tributes {
+            errors.push(EventError::new(location, format!(
+                "`when` index {} is out of range for {} tributes", idx, action.tributes)));
+            ok = false;
+        }
+    }
+    for (field, statuses) in [("applies", &action.applies), ("cures", &action.cures)].iter() {
+        for idx in statuses.keys() {
+            if *idx >= action.tributes {
+                errors.push(EventError::new(location, format!(
+                    "`{}` index {} is out of range for {} tributes", field, idx, action.tributes)));
+                ok = false;
+            }
+        }
+    }
+    let context = tributes_context(action.tributes);
+    ok &= check_template(location, "msg", &action.msg, &context, errors);
+    for (code, msg) in action.msgs.iter() {
+        ok &= check_template(location, &format!("msgs.{}", code), msg, &context, errors);
+    }
+    if ok { Some(action) } else { None }
+}
+
+fn parse_custom(location: &str, v: &Value, errors: &mut Vec<EventError>) -> Option<CustomRound> {
+    let name = match v.get("name").and_then(|n| n.as_str()) {
+        Some(name) if !name.trim().is_empty() => name.to_string(),
+        _ => {
+            errors.push(EventError::new(location, String::from("missing `name` string")));
+            return None;
+        }
+    };
+    let location = format!("{} ({})", location, name);
+    let schedule: Schedule = match serde_json::from_value(v.clone()) {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            errors.push(EventError::new(&location, e.to_string()));
+            return None;
+        }
+    };
+    let n_errors = errors.len();
+    if BUILTIN_ROUNDS.contains(&name.as_str()) {
+        errors.push(EventError::new(&location, format!("`{}` is a built-in round", name)));
+    }
+    if !(0.0..=1.0).contains(&schedule.chance) {
+        errors.push(EventError::new(&location, format!("`chance` must be between 0 and 1, found {}", schedule.chance)));
+    }
+    if schedule.after == name && schedule.max_per_game.is_none() {
+        errors.push(EventError::new(&location, String::from("a round that follows itself needs `max_per_game`")));
+    }
+    let events = parse_round(&location, Some(v), errors);
+    if errors.len() > n_errors {
+        return None;
+    }
+    events.map(|events| CustomRound{name, schedule, events})
+}
+
+fn parse_round(location: &str, v: Option<&Value>, errors: &mut Vec<EventError>) -> Option<RoundEvents> {
+    let v = match v {
+        Some(v) => v,
+        None => {
+            errors.push(EventError::new(location, String::from("missing round")));
+            return None;
+        }
+    };
+    let raw: RawRound = match serde_json::from_value(v.clone()) {
+        Ok(raw) => raw,
+        Err(e) => {
+            errors.push(EventError::new(location, e.to_string()));
+            return None;
+        }
+    };
+    let n_errors = errors.len();
+    if raw.id.as_ref().is_some_and(|id| id.trim().is_empty()) {
+        errors.push(EventError::new(location, String::from("`id` is empty")));
+    }
+    if !(raw.weight > 0.0 && raw.weight.is_finite()) {
+        errors.push(EventError::new(location, format!("`weight` must be a positive number, found {}", raw.weight)));
+    }
+    if raw.nonfatal.is_empty() {
+        errors.push(EventError::new(location, String::from("needs at least one nonfatal action")));
+    }
+    let context = json!({ "0": 1 });
+    check_template(location, "title", &raw.title, &context, errors);
+    for (code, title) in raw.titles.iter() {
+        check_template(location, &format!("titles.{}", code), title, &context, errors);
+    }
+    let actions = |kind: &str, values: Vec<Value>, errors: &mut Vec<EventError>| -> Vec<Action> {
+        values.into_iter().enumerate()
+            .filter_map(|(i, a)| parse_action(&format!("{}.{}[{}]", location, kind, i), a, errors))
+            .collect()
+    };
+    let nonfatal = actions("nonfatal", raw.nonfatal, errors);
+    let fatal = actions("fatal", raw.fatal, errors);
+    if errors.len() > n_errors {
+        return None;
+    }
+    Some(RoundEvents{id:raw.id, title:raw.title, titles:raw.titles, description:raw.description, color:raw.color, weight:raw.weight, nonfatal, fatal})
+}
+
+/// The name a pack goes by in reports, its file name
+pub fn pack_name(path: &Path) -> String {
+    path.file_name().map(|f| f.to_string_lossy().into_owned()).unwrap_or_else(|| path.display().to_string())
+}
+
+// the sections of a fixed round an add-on may extend, all optional
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawAddition {
+    title: Option<String>,
+    #[serde(default)]
+    titles: BTreeMap<String, String>,
+    description: Option<String>,
+    color: Option<String>,
+    #[serde(default)]
+    nonfatal: Vec<Value>,
+    #[serde(default)]
+    fatal: Vec<Value>,
+}
+
+/// Adds an add-on's section to a fixed round, replacing actions by id;
+/// `ids` maps the ids of earlier packs to their locations, and `seen` those
+/// of this add-on so far
+fn merge_round(location: &str, round: &mut RoundEvents, v: &Value, ids: &BTreeMap<String, String>,
+        seen: &mut BTreeMap<String, String>, errors: &mut Vec<EventError>) {
+    let raw: RawAddition = match serde_json::from_value(v.clone()) {
+        Ok(raw) => raw,
+        Err(e) => {
+            errors.push(EventError::new(location, e.to_string()));
+            return;
+        }
+    };
+    let context = json!({ "0": 1 });
+    for (field, title) in raw.title.iter().map(|t| (String::from("title"), t))
+            .chain(raw.titles.iter().map(|(code, t)| (format!("titles.{}", code), t))) {
+        check_template(location, &field, title, &context, errors);
+    }
+    if let Some(title) = raw.title {
+        round.title = title;
+        round.titles.clear();
+    }
+    round.titles.extend(raw.titles);
+    if let Some(description) = raw.description {
+        round.description = description;
+    }
+    if let Some(color) = raw.color {
+        round.color = color;
+    }
+    for (kind, values, list) in [("nonfatal", raw.nonfatal, &mut round.nonfatal), ("fatal", raw.fatal, &mut round.fatal)] {
+        for (i, v) in values.into_iter().enumerate() {
+            let action_location = format!("{}.{}[{}]", location, kind, i);
+            let action = match parse_action(&action_location, v, errors) {
+                Some(action) => action,
+                None => continue,
+            };
+            if !add_id(&action_location, action.id.as_ref(), seen, errors) {
+                continue;
+            }
+            let existing = action.id.as_ref().and_then(|id| list.iter().position(|a| a.id.as_ref() == Some(id)));
+            match (existing, action.id.as_ref().and_then(|id| ids.get(id))) {
+                (Some(pos), _) => list[pos] = action,
+                (None, Some(other)) => errors.push(EventError::new(&action_location, format!(
+                    "id `{}` is used at {}, only actions of the same round and kind can be replaced",
+                    action.id.as_ref().unwrap(), other))),
+                (None, None) => list.push(action),
+            }
+        }
+    }
+}
+
+/// Records the id of an add-on's action or arena event, failing if the
+/// add-on already used it
+fn add_id(location: &str, id: Option<&String>, seen: &mut BTreeMap<String, String>, errors: &mut Vec<EventError>) -> bool {
+    let id = match id {
+        Some(id) => id,
+        None => return true,
+    };
+    match seen.get(id) {
+        Some(first) => {
+            errors.push(EventError::new(location, format!("id `{}` is already used at {}", id, first)));
+            false
+        }
+        None => {
+            seen.insert(id.clone(), location.to_string());
+            true
+        }
+    }
+}
+
+/// The formats an event pack can be written in, picked by file extension
+///
+/// TOML and YAML are for authoring; they read into the same JSON value as
+/// `events.json`, so a pack converts to and from them without loss.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PackFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl PackFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PackFormat::Json => "JSON",
+            PackFormat::Toml => "TOML",
+            PackFormat::Yaml => "YAML",
+        }
+    }
+
+    /// `.toml`, `.yaml` or `.yml`, and JSON for anything else
+    pub fn from_path(path: &Path) -> PackFormat {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => PackFormat::Toml,
+            Some("yaml") | Some("yml") => PackFormat::Yaml,
+            _ => PackFormat::Json,
+        }
+    }
+
+    pub fn parse(&self, data: &str) -> Result<Value, String> {
+        match self {
+            PackFormat::Json => serde_json::from_str(data).map_err(|e| e.to_string()),
+            PackFormat::Toml => toml::from_str(data).map_err(|e| e.to_string()),
+            PackFormat::Yaml => serde_yaml::from_str(data).map_err(|e| e.to_string()).and_then(yaml_to_json),
+        }
+    }
+
+    pub fn write(&self, v: &Value) -> Result<String, String> {
+        match self {
+            PackFormat::Json => {
+                let mut out: Vec<u8> = Vec::new();
+                let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+                let mut ser = serde_json::Serializer::with_formatter(&mut out, formatter);
+                v.serialize(&mut ser).map_err(|e| e.to_string())?;
+                out.push(b'\n');
+                String::from_utf8(out).map_err(|e| e.to_string())
+            }
+            PackFormat::Toml => toml::Value::try_from(v)
+                .and_then(|t| toml::to_string_pretty(&t))
+                .map_err(|e| e.to_string()),
+            PackFormat::Yaml => serde_yaml::to_string(v).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// YAML allows keys that are not strings, e.g. `0:` in a `requires` map,
+/// which JSON spells `"0"`
+fn yaml_to_json(v: serde_yaml::Value) -> Result<Value, String> {
+    Ok(match v {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(b) => Value::Bool(b),
+        serde_yaml::Value::Number(n) => match (n.as_u64(), n.as_i64(), n.as_f64()) {
+            (Some(u), _, _) => Value::from(u),
+            (None, Some(i), _) => Value::from(i),
+            (None, None, Some(f)) => serde_json::Number::from_f64(f).map(Value::Number)
+                .ok_or_else(|| format!("{} is not a valid number", f))?,
+            _ => return Err(format!("{} is not a valid number", n)),
+        },
+        serde_yaml::Value::String(s) => Value::String(s),
+        serde_yaml::Value::Sequence(items) => Value::Array(items.into_iter().map(yaml_to_json).collect::<Result<_, _>>()?),
+        serde_yaml::Value::Mapping(map) => {
+            let mut object = serde_json::Map::new();
+            for (k, v) in map.into_iter() {
+                let key = match k {
+                    serde_yaml::Value::String(s) => s,
+                    serde_yaml::Value::Number(n) => n.to_string(),
+                    serde_yaml::Value::Bool(b) => b.to_string(),
+                    other => return Err(format!("map keys must be strings or numbers, found {:?}", other)),
+                };
+                object.insert(key, yaml_to_json(v)?);
+            }
+            Value::Object(object)
+        }
+    })
+}
+
+/// Reads an event pack in any `PackFormat` into its JSON form
+pub fn read_pack(path: &Path) -> Result<Value, EventError> {
+    let data = fs::read_to_string(path)
+        .map_err(|e| EventError::new(&path.display().to_string(), e.to_string()))?;
+    let format = PackFormat::from_path(path);
+    format.parse(&data).map_err(|e| EventError::new("", format!("invalid {}: {}", format.as_str(), e)))
+}
+
+impl EventSet {
+    /// Reads and checks an event pack, reporting every malformed entry at once
+    pub fn load(path: &Path) -> Result<EventSet, Vec<EventError>> {
+        let v = read_pack(path).map_err(|e| vec![e])?;
+        EventSet::from_value(&v)
+    }
+
+    pub fn from_value(v: &Value) -> Result<EventSet, Vec<EventError>> {
+        let mut errors: Vec<EventError> = Vec::new();
+        let bloodbath = parse_round("bloodbath", v.get("bloodbath"), &mut errors);
+        let day = parse_round("day", v.get("day"), &mut errors);
+        let night = parse_round("night", v.get("night"), &mut errors);
+        let feast = parse_round("feast", v.get("feast"), &mut errors);
+
+        let mut arena: Vec<RoundEvents> = Vec::new();
+        match v.get("arena") {
+            Some(Value::Array(arena_values)) => {
+                for (i, a) in arena_values.iter().enumerate() {
+                    if let Some(round) = parse_round(&format!("arena[{}]", i), Some(a), &mut errors) {
+                        arena.push(round);
+                    }
+                }
+            }
+            Some(_) => errors.push(EventError::new("arena", String::from("expected an array of events"))),
+            None => ()
+        }
+
+        let mut custom: Vec<CustomRound> = Vec::new();
+        match v.get("custom") {
+            Some(Value::Array(custom_values)) => {
+                for (i, c) in custom_values.iter().enumerate() {
+                    if let Some(round) = parse_custom(&format!("custom[{}]", i), c, &mut errors) {
+                        custom.push(round);
+                    }
+                }
+            }
+            Some(_) => errors.push(EventError::new("custom", String::from("expected an array of rounds"))),
+            None => ()
+        }
+
+        match (bloodbath, day, night, feast) {
+            (Some(bloodbath), Some(day), Some(night), Some(feast)) if errors.is_empty() => {
+                let events = EventSet{bloodbath, day, night, feast, arena, custom};
+                events.check(&mut errors);
+                if errors.is_empty() { Ok(events) } else { Err(errors) }
+            }
+            _ => Err(errors)
+        }
+    }
+
+    /// Checks what only shows once every round is in place: custom round
+    /// names and `after` references, duplicate ids and empty rounds
+    fn check(&self, errors: &mut Vec<EventError>) {
+        let custom = &self.custom;
+        for (i, c) in custom.iter().enumerate() {
+            let location = format!("custom ({})", c.name);
+            if custom[..i].iter().any(|other| other.name == c.name) {
+                errors.push(EventError::new(&location, String::from("duplicate round name")));
+            }
+            if !BUILTIN_ROUNDS.contains(&c.schedule.after.as_str()) && !custom.iter().any(|other| other.name == c.schedule.after) {
+                errors.push(EventError::new(&location, format!("`after` names unknown round `{}`", c.schedule.after)));
+            }
+        }
+        let mut ids: BTreeMap<&str, String> = BTreeMap::new();
+        let arena_ids = self.arena.iter().enumerate().map(|(i, a)| (format!("arena[{}]", i), &a.id));
+        let action_ids = self.actions().into_iter().map(|(location, a)| (location, &a.id));
+        for (location, id) in arena_ids.chain(action_ids) {
+            if let Some(id) = id {
+                match ids.get(id.as_str()) {
+                    Some(first) => errors.push(EventError::new(&location, format!("id `{}` is already used at {}", id, first))),
+                    None => {
+                        ids.insert(id, location);
+                    }
+                }
+            }
+        }
+        for (location, round) in self.rounds() {
+            if round.nonfatal.is_empty() {
+                errors.push(EventError::new(&location, String::from("needs at least one nonfatal action")));
+            }
+        }
+    }
+
+    /// Every round with its location: the fixed rounds, then the arena
+    /// events and the custom rounds
+    pub fn rounds(&self) -> Vec<(String, &RoundEvents)> {
+        let mut rounds: Vec<(String, &RoundEvents)> = vec![
+            (String::from("bloodbath"), &self.bloodbath), (String::from("day"), &self.day),
+            (String::from("night"), &self.night), (String::from("feast"), &self.feast)];
+        rounds.extend(self.arena.iter().enumerate().map(|(i, a)| (format!("arena[{}]", i), a)));
+        rounds.extend(self.custom.iter().map(|c| (format!("custom ({})", c.name), &c.events)));
+        rounds
+    }
+
+    fn rounds_mut(&mut self) -> Vec<&mut RoundEvents> {
+        let mut rounds: Vec<&mut RoundEvents> = vec![&mut self.bloodbath, &mut self.day, &mut self.night, &mut self.feast];
+        rounds.extend(self.arena.iter_mut());
+        rounds.extend(self.custom.iter_mut().map(|c| &mut c.events));
+        rounds
+    }
+
+    /// Every action with its location, e.g. `day.fatal[12]`
+    pub fn actions(&self) -> Vec<(String, &Action)> {
+        let mut actions: Vec<(String, &Action)> = Vec::new();
+        for (location, round) in self.rounds() {
+            for (kind, list) in [("nonfatal", &round.nonfatal), ("fatal", &round.fatal)].iter() {
+                actions.extend(list.iter().enumerate().map(|(i, a)| (format!("{}.{}[{}]", location, kind, i), a)));
+            }
+        }
+        actions
+    }
+
+    /// Records `pack` as the source of every action not yet attributed
+    fn set_pack(&mut self, pack: &str) {
+        for round in self.rounds_mut() {
+            for action in round.nonfatal.iter_mut().chain(round.fatal.iter_mut()) {
+                if action.pack.is_empty() {
+                    action.pack = pack.to_string();
+                }
+            }
+        }
+    }
+
+    /// Loads a base pack followed by add-on packs, see `merge`
+    pub fn load_packs<'a>(paths: &[&'a Path]) -> Result<EventSet, (&'a Path, Vec<EventError>)> {
+        let (base, addons) = match paths.split_first() {
+            Some(split) => split,
+            None => return Err((Path::new(""), vec![EventError::new("", String::from("no event pack given"))])),
+        };
+        let mut events = EventSet::load(base).map_err(|errors| (*base, errors))?;
+        events.set_pack(&pack_name(base));
+        for path in addons.iter() {
+            events.merge_file(path).map_err(|errors| (*path, errors))?;
+        }
+        Ok(events)
+    }
+
+    pub fn merge_file(&mut self, path: &Path) -> Result<(), Vec<EventError>> {
+        let v = read_pack(path).map_err(|e| vec![e])?;
+        self.merge(&v, &pack_name(path))
+    }
+
+    /// Adds an add-on pack on top of this one
+    ///
+    /// Every section of an add-on is optional. Actions in a fixed round are
+    /// appended to that round, unless their `id` matches an action of the
+    /// same round and kind, which they replace. A title given for a fixed
+    /// round replaces the old one. Arena events and custom rounds are
+    /// appended, unless an arena event's `id` matches an earlier one, which it
+    /// replaces. `disable` lists the ids of earlier actions and arena events
+    /// to drop. Nothing changes unless the whole add-on is valid.
+    pub fn merge(&mut self, v: &Value, pack: &str) -> Result<(), Vec<EventError>> {
+        let mut errors: Vec<EventError> = Vec::new();
+        let mut merged = self.clone();
+        if !v.is_object() {
+            return Err(vec![EventError::new("", String::from("an event pack must be a JSON object"))]);
+        }
+
+        match v.get("disable") {
+            Some(Value::Array(ids)) => {
+                for id in ids.iter() {
+                    match id.as_str() {
+                        Some(id) if merged.disable(id) => (),
+                        Some(id) => errors.push(EventError::new("disable", format!("no earlier action has id `{}`", id))),
+                        None => errors.push(EventError::new("disable", format!("expected an action id, found {}", id))),
+                    }
+                }
+            }
+            Some(_) => errors.push(EventError::new("disable", String::from("expected an array of action ids"))),
+            None => ()
+        }
+
+        let ids: BTreeMap<String, String> = merged.actions().into_iter()
+            .filter_map(|(location, a)| a.id.clone().map(|id| (id, location)))
+            .chain(merged.arena.iter().enumerate().filter_map(|(i, a)| a.id.clone().map(|id| (id, format!("arena[{}]", i)))))
+            .collect();
+        let mut seen: BTreeMap<String, String> = BTreeMap::new();
+        for key in ["bloodbath", "day", "night", "feast"].iter() {
+            if let Some(addition) = v.get(*key) {
+                let round = match *key {
+                    "bloodbath" => &mut merged.bloodbath,
+                    "day" => &mut merged.day,
+                    "night" => &mut merged.night,
+                    _ => &mut merged.feast,
+                };
+                merge_round(key, round, addition, &ids, &mut seen, &mut errors);
+            }
+        }
+        match v.get("arena") {
+            Some(Value::Array(arena_values)) => {
+                for (i, a) in arena_values.iter().enumerate() {
+                    let location = format!("arena[{}]", i);
+                    let round = match parse_round(&location, Some(a), &mut errors) {
+                        Some(round) => round,
+                        None => continue,
+                    };
+                    if !add_id(&location, round.id.as_ref(), &mut seen, &mut errors) {
+                        continue;
+                    }
+                    match merged.arena.iter().position(|other| other.id.is_some() && other.id == round.id) {
+                        Some(pos) => merged.arena[pos] = round,
+                        None => merged.arena.push(round),
+                    }
+                }
+            }
+            Some(_) => errors.push(EventError::new("arena", String::from("expected an array of events"))),
+            None => ()
+        }
+        match v.get("custom") {
+            Some(Value::Array(custom_values)) => {
+                let n_custom = merged.custom.len();
+                for (i, c) in custom_values.iter().enumerate() {
+                    if let Some(round) = parse_custom(&format!("custom[{}]", n_custom + i), c, &mut errors) {
+                        merged.custom.push(round);
+                    }
+                }
+            }
+            Some(_) => errors.push(EventError::new("custom", String::from("expected an array of rounds"))),
+            None => ()
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        merged.set_pack(pack);
+        merged.check(&mut errors);
+        if errors.is_empty() {
+            *self = merged;
+            Ok(())
+        }
+        else {
+            Err(errors)
+        }
+    }
+
+    /// Drops the arena event or every action with this id, returning false
+    /// if there was none
+    fn disable(&mut self, id: &str) -> bool {
+        let n_arena = self.arena.len();
+        self.arena.retain(|a| a.id.as_deref() != Some(id));
+        let mut found = self.arena.len() < n_arena;
+        for round in self.rounds_mut() {
+            for list in [&mut round.nonfatal, &mut round.fatal] {
+                let before = list.len();
+                list.retain(|a| a.id.as_deref() != Some(id));
+                found |= list.len() < before;
+            }
+        }
+        found
+    }
+
+    /// The fixed round for a round type; ARENA rounds are drawn from `arena`
+    pub fn round(&self, round_type: RoundType) -> Option<&RoundEvents> {
+        match round_type {
+            RoundType::BLOODBATH => Some(&self.bloodbath),
+            RoundType::DAY => Some(&self.day),
+            RoundType::NIGHT => Some(&self.night),
+            RoundType::FEAST => Some(&self.feast),
+            RoundType::CUSTOM(i) => self.custom.get(i).map(|c| &c.events),
+            _ => None,
+        }
+    }
+
+    /// The key a round type goes by, a custom round's name for custom rounds
+    pub fn round_name(&self, round_type: RoundType) -> &str {
+        match round_type {
+            RoundType::CUSTOM(i) => &self.custom[i].name,
+            _ => round_type.as_str(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn action(v: Value) -> Action {
+        serde_json::from_value(v).unwrap()
+    }
+
+    fn round(nonfatal: Value) -> Value {
+        json!({ "title": "Day {0}", "nonfatal": nonfatal, "fatal": [] })
+    }
+
+    /// A base pack with two day actions and two arena events, all with ids
+    fn base() -> EventSet {
+        let rest = json!([{ "msg": "{0.name} rests.", "tributes": 1 }]);
+        let day = json!([
+            { "id": "rest", "msg": "{0.name} rests.", "tributes": 1 },
+            { "id": "hunt", "msg": "{0.name} hunts.", "tributes": 1 },
+        ]);
+        let mut events = EventSet::from_value(&json!({
+            "bloodbath": round(rest.clone()), "day": round(day), "night": round(rest.clone()), "feast": round(rest.clone()),
+            "arena": [
+                { "id": "flood", "title": "A flood", "nonfatal": rest, "fatal": [] },
+                { "id": "fire", "title": "A fire", "nonfatal": [{ "msg": "{0.name} runs.", "tributes": 1 }], "fatal": [] },
+            ],
+        })).unwrap();
+        events.set_pack("base.json");
+        events
+    }
+
+    fn msgs(actions: &[Action]) -> Vec<&str> {
+        actions.iter().map(|a| a.msg.as_str()).collect()
+    }
+
+    #[test]
+    fn bundled_packs_convert_between_formats_without_loss() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("data");
+        for path in [dir.join("events.json"), dir.join("packs").join("holiday.toml")].iter() {
+            let v = read_pack(path).unwrap();
+            for format in [PackFormat::Json, PackFormat::Toml, PackFormat::Yaml].iter() {
+                let text = format.write(&v).unwrap();
+                assert_eq!(format.parse(&text).unwrap(), v, "{} via {}", path.display(), format.as_str());
+            }
+        }
+    }
+
+    #[test]
+    fn yaml_index_keys_read_as_strings() {
+        let v = PackFormat::Yaml.parse("requires:\n  0: [bow]\nweight: 0.5\n").unwrap();
+        assert_eq!(v, json!({ "requires": { "0": ["bow"] }, "weight": 0.5 }));
+        assert_eq!(PackFormat::from_path(Path::new("pack.yml")), PackFormat::Yaml);
+        assert_eq!(PackFormat::from_path(Path::new("pack.toml")), PackFormat::Toml);
+        assert_eq!(PackFormat::from_path(Path::new("pack")), PackFormat::Json);
+    }
+
+    #[test]
+    fn templates_are_checked_on_load() {
+        let rest = json!([{ "msg": "{0.name} rests.", "tributes": 1 }]);
+        let day = json!([
+            { "msg": "{0.nmae} rests.", "tributes": 1 },
+            { "msg": "{1.name} rests.", "tributes": 1 },
+            { "msg": "{0.name} rests.", "msgs": { "de": "{0.name ruht." }, "tributes": 1 },
+        ]);
+        let errors = EventSet::from_value(&json!({
+            "bloodbath": round(rest.clone()), "day": round(day), "night": round(rest.clone()), "feast": round(rest),
+        })).unwrap_err();
+        let locations: Vec<&str> = errors.iter().map(|e| e.location.as_str()).collect();
+        assert_eq!(locations, vec!["day.nonfatal[0]", "day.nonfatal[1]", "day.nonfatal[2]"]);
+        assert!(errors[2].message.starts_with("`msgs.de` is not a valid template"));
+    }
+
+    #[test]
+    fn merge_replaces_appends_and_disables_by_id() {
+        let mut events = base();
+        let addon = json!({
+            "disable": ["fire"],
+            "day": { "nonfatal": [
+                { "id": "hunt", "msg": "{0.name} hunts at dusk.", "tributes": 1 },
+                { "id": "fish", "msg": "{0.name} fishes.", "tributes": 1 },
+            ] },
+            "arena": [{ "id": "flood", "title": "A great flood", "nonfatal": [{ "msg": "{0.name} swims.", "tributes": 1 }],
+                "fatal": [] }],
+        });
+        events.merge(&addon, "addon.json").unwrap();
+        assert_eq!(msgs(&events.day.nonfatal), vec!["{0.name} rests.", "{0.name} hunts at dusk.", "{0.name} fishes."]);
+        let packs: Vec<&str> = events.day.nonfatal.iter().map(|a| a.pack.as_str()).collect();
+        assert_eq!(packs, vec!["base.json", "addon.json", "addon.json"]);
+        let arena: Vec<&str> = events.arena.iter().map(|a| a.title.as_str()).collect();
+        assert_eq!(arena, vec!["A great flood"]);
+
+        events.merge(&json!({ "disable": ["rest"] }), "more.json").unwrap();
+        assert_eq!(msgs(&events.day.nonfatal), vec!["{0.name} hunts at dusk.", "{0.name} fishes."]);
+    }
+
+    #[test]
+    fn failed_merge_leaves_the_pack_unchanged() {
+        let mut events = base();
+        for addon in [
+            json!({ "disable": ["nothing"] }),
+            json!({ "night": { "nonfatal": [{ "id": "hunt", "msg": "{0.name} hunts.", "tributes": 1 }] } }),
+            json!({ "day": { "nonfatal": [
+                { "id": "fish", "msg": "{0.name} fishes.", "tributes": 1 },
+                { "id": "fish", "msg": "{0.name} fishes again.", "tributes": 1 },
+            ] } }),
+            json!({ "arena": [
+                { "id": "storm", "title": "A storm", "nonfatal": [{ "msg": "{0.name} hides.", "tributes": 1 }], "fatal": [] },
+                { "id": "storm", "title": "A storm", "nonfatal": [{ "msg": "{0.name} hides.", "tributes": 1 }], "fatal": [] },
+            ] }),
+            json!({ "day": { "nonfatal": [{ "msg": "{0.name} waits.", "tributes": 0 }] } }),
+        ].iter() {
+            assert!(events.merge(addon, "addon.json").is_err(), "{} was accepted", addon);
+            assert_eq!(msgs(&events.day.nonfatal), vec!["{0.name} rests.", "{0.name} hunts."]);
+            assert_eq!(events.arena.len(), 2);
+        }
+        let errors = events.merge(&json!({ "day": { "nonfatal": [
+            { "id": "fish", "msg": "{0.name} fishes.", "tributes": 1 },
+            { "id": "fish", "msg": "{0.name} fishes again.", "tributes": 1 },
+        ] } }), "addon.json").unwrap_err();
+        assert_eq!(errors[0].to_string(), "day.nonfatal[1]: id `fish` is already used at day.nonfatal[0]");
+    }
+
+    #[test]
+    fn needs_counts_a_tag_both_required_and_consumed_once() {
+        let a = action(json!({ "msg": "{0.name} shoots.", "tributes": 1,
+            "requires": { "0": ["bow"] }, "consumes": { "0": ["bow", "arrow"] } }));
+        assert_eq!(a.needs(0), vec!["bow", "arrow"]);
+        let a = action(json!({ "msg": "{0.name} eats.", "tributes": 1,
+            "requires": { "0": ["food"] }, "consumes": { "0": ["food", "food"] } }));
+        assert_eq!(a.needs(0), vec!["food", "food"]);
+        assert!(a.needs(1).is_empty());
+    }
+}
@@ -1,22 +1,30 @@
+use std::collections::HashMap;
 use std::io;
-use std::fs;
 use rand::Rng;
 use rand::seq::SliceRandom;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use serde_json::{Value, Map, json};
 use tinytemplate::TinyTemplate;
-use std::process; // temporary
 
 use super::roster;
-use super::img;
-
-#[derive(PartialEq)]
-enum RoundType {
+use super::tribute::Status;
+use super::output::Output;
+use super::events::{EventSet, RoundEvents, Action};
+use super::settings::Rules;
+use super::locale::{Locale, Strings, fill};
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RoundType {
     BLOODBATH,
     FEAST,
     ARENA,
     DAY,
     NIGHT,
     FALLEN,
+    /// a round declared in the event pack's `custom` list, by index
+    CUSTOM(usize),
     NONE
 }
 
@@ -29,262 +37,795 @@ impl RoundType {
             RoundType::DAY => "day",
             RoundType::NIGHT => "night",
             RoundType::FALLEN => "fallen",
+            RoundType::CUSTOM(_) => "custom",
             RoundType::NONE => "none",
         }
     }
 }
 
-pub fn gameloop(game_roster: &mut roster::Roster) -> i32 {
-    let status: i32 = 0;
+/// One rendered action within a round, participants given as roster indices
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActionResult {
+    /// the action's `id` in the event pack, if it has one
+    pub id: Option<String>,
+    pub message: String,
+    pub members: Vec<usize>,
+    pub killers: Vec<usize>,
+    pub killed: Vec<usize>,
+}
 
-    let mut day: i32 = 1;
-    let mut n_alive: i32;
-    let mut days_since_last_event: i32 = 0;
-    let mut consecutive_rounds_without_deaths: i32 = 0;
+/// Everything that happened in a single call to `Simulation::step`
+///
+/// For FALLEN rounds `members` holds the tributes who died that day and
+/// `actions` the deaths from statuses that happened off-screen.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoundResult {
+    pub day: i32,
+    pub round_type: RoundType,
+    /// `round_type` as a string, a custom round's own name for custom rounds
+    pub name: String,
+    /// the `id` of the arena event played, if it has one
+    pub event_id: Option<String>,
+    pub title: String,
+    pub members: Vec<usize>,
+    pub actions: Vec<ActionResult>,
+}
 
-    let mut bloodbath_passed: bool = false;
-    let mut day_passed: bool = false;
-    let mut fallen_passed: bool = false;
-    let mut night_passed: bool = false;
+/// Renders an event template, falling back to the raw text if it is malformed
+///
+/// `EventSet` checks every event template as it loads, so only locale and
+/// gift text can still fall back here.
+fn render<C: serde::Serialize>(template: &str, context: &C) -> String {
+    let mut tt = TinyTemplate::new();
+    tt.add_template("tmp", template)
+        .and_then(|_| tt.render("tmp", context))
+        .unwrap_or_else(|_| template.to_string())
+}
 
-    let mut rng = rand::thread_rng();
+/// Picks a fresh seed for games where none was requested
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen()
+}
 
-    game_roster.default_gender_setup();
+/// Builds the simulation RNG from a seed
+pub fn seeded_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+/// How much likelier an ally is to fill a slot where allies are wanted, and
+/// how much less likely where they are not
+const ALLY_BIAS: f64 = 4.0;
 
-    // read in the events...
-    let data = fs::read_to_string("data/events.json")
-        .expect("Something went wrong reading the file");
+/// True when one of the two participant slots kills the other
+fn opposed(action: &Action, a: usize, b: usize) -> bool {
+    (action.killer.contains(&a) && action.killed.contains(&b)) ||
+        (action.killed.contains(&a) && action.killer.contains(&b))
+}
 
-    let mut events: Value = Value::Null;
-    match serde_json::from_str(data.as_str()) {
-        Ok(result) => events = result,
-        Err(e) => println!("Error {}", e)
+/// Fills the participant slots of `action` in order from the available
+/// tributes, weighting each pick by alliances with those already chosen
+///
+/// Allies are preferred among the `alliance` slots and in betrayals, and
+/// avoided as each other's victims otherwise. Statuses make a tribute a more
+/// or less likely victim, and hidden tributes are unlikely to take part in
+/// fatal actions at all. Stats favour strong tributes for the winning side and
+/// weak ones for the losing side. Only tributes carrying the items a slot
+/// needs and meeting its `when` conditions can fill it; `None` means no one
+/// could.
+fn pick_members(game_roster: &roster::Roster, action: &Action, rng: &mut StdRng) -> Option<Vec<usize>> {
+    let mut members: Vec<usize> = Vec::with_capacity(action.tributes);
+    for slot in 0..action.tributes {
+        let needs = action.needs(slot);
+        let candidates: Vec<usize> = (0..game_roster.len())
+            .filter(|&i| game_roster.get_available(i) && !members.contains(&i))
+            .filter(|&i| game_roster.get_tribute(i).has_items(&needs))
+            .filter(|&i| action.when.allow_member(slot, game_roster.get_tribute(i)))
+            .collect();
+        let weight = |&c: &usize| -> f64 {
+            let mut w = 1.0;
+            let tb = game_roster.get_tribute(c);
+            if action.wins(slot) {
+                w *= tb.stats.winner_weight(action.stat);
+            }
+            else if action.loses(slot) {
+                w *= tb.stats.loser_weight(action.stat);
+            }
+            let statuses = &tb.statuses;
+            if action.killed.contains(&slot) {
+                w *= statuses.iter().map(|s| s.victim_weight()).product::<f64>();
+            }
+            else if !action.killed.is_empty() && statuses.contains(&Status::Hidden) {
+                w *= Status::Hidden.victim_weight();
+            }
+            for (other, &m) in members.iter().enumerate() {
+                if !game_roster.are_allies(c, m) {
+                    continue;
+                }
+                if action.betray || (action.alliance.contains(&slot) && action.alliance.contains(&other)) {
+                    w *= ALLY_BIAS;
+                }
+                else if opposed(action, slot, other) {
+                    w /= ALLY_BIAS;
+                }
+            }
+            w
+        };
+        members.push(*candidates.choose_weighted(rng, weight).ok()?);
     }
-    // let mut eventlog
+    Some(members)
+}
 
-    let mut imgidx: u32 = 0;
-    loop {
-        let mut tt = TinyTemplate::new();
-        let mut input = String::new();
-        match io::stdin().read_line(&mut input) {
-            Ok(_) => {},
-            Err(error) => println!("error: {}", error),
+/// Where an action sits in the event pack: round type, arena event index
+/// (0 outside the arena), whether it is fatal, and its position in that list
+type ActionKey = (RoundType, usize, bool, usize);
+
+/// Whether `once_per_game` and `cooldown` let an action last used in played
+/// round `used` appear in round `round_no`
+fn can_reuse(action: &Action, used: Option<&i32>, round_no: i32) -> bool {
+    match used {
+        None => true,
+        Some(_) if action.once_per_game => false,
+        Some(&used) => action.cooldown.is_none_or(|cooldown| round_no - used > cooldown as i32),
+    }
+}
+
+/// Draws an action by weight among those `usable` allows
+fn pick_action<'a, F>(actions: &'a [Action], usable: F, rng: &mut StdRng) -> Option<(usize, &'a Action)>
+where
+    F: Fn(usize, &Action) -> bool,
+{
+    let eligible: Vec<(usize, &Action)> = actions.iter().enumerate()
+        .filter(|(i, a)| usable(*i, a))
+        .collect();
+    eligible.choose_weighted(rng, |(_, a)| a.weight).ok().copied()
+}
+
+/// Draws in a row that may fail to place anyone before a round gives up on
+/// the tributes still waiting, e.g. when none of them carries what the only
+/// small enough action needs
+const MAX_FAILED_DRAWS: u32 = 1000;
+
+/// A game in progress, advanced one round at a time with `step`
+///
+/// Every random decision is drawn from a single RNG seeded at construction,
+/// so the same roster, events and seed always replay the same game.
+pub struct Simulation {
+    roster: roster::Roster,
+    events: EventSet,
+    rules: Rules,
+    rng: StdRng,
+    seed: u64,
+    day: i32,
+    days_since_last_event: i32,
+    consecutive_rounds_without_deaths: i32,
+    bloodbath_passed: bool,
+    day_passed: bool,
+    fallen_passed: bool,
+    night_passed: bool,
+    night_fallen_passed: bool,
+    feast_held: bool,
+    /// tributes already reported in a FALLEN round
+    reported_dead: Vec<usize>,
+    /// custom rounds due to be played next, by index
+    pending_custom: Vec<usize>,
+    /// times each custom round has been scheduled
+    custom_played: Vec<u32>,
+    /// rounds with actions played so far
+    rounds_played: i32,
+    /// action -> the played round it was last used in
+    last_used: HashMap<ActionKey, i32>,
+    /// what is left of the sponsors' budget
+    sponsor_budget: u32,
+    locale: Locale,
+}
+
+impl Simulation {
+    pub fn new(mut game_roster: roster::Roster, events: EventSet, rules: Rules, seed: u64) -> Simulation {
+        let locale = Locale::default();
+        game_roster.default_gender_setup(&locale);
+        let n_custom = events.custom.len();
+        let sponsor_budget = rules.sponsors.budget;
+        Simulation{roster:game_roster, events, rules, rng:seeded_rng(seed), seed, day:1,
+            days_since_last_event:0, consecutive_rounds_without_deaths:0,
+            bloodbath_passed:false, day_passed:false, fallen_passed:false, night_passed:false,
+            night_fallen_passed:false, feast_held:false, reported_dead:Vec::new(),
+            pending_custom:Vec::new(), custom_played:vec![0; n_custom], rounds_played:0, last_used:HashMap::new(),
+            sponsor_budget, locale}
+    }
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    pub fn day(&self) -> i32 {
+        self.day
+    }
+    pub fn roster(&self) -> &roster::Roster {
+        &self.roster
+    }
+    pub fn into_roster(self) -> roster::Roster {
+        self.roster
+    }
+    /// True once fewer than two tributes remain or the last day is over
+    pub fn is_finished(&self) -> bool {
+        self.roster.n_alive() < 2 || (self.day >= self.rules.max_days && self.day_over())
+    }
+    /// Whether the current day's rounds have all been played
+    fn day_over(&self) -> bool {
+        self.night_passed && (self.night_fallen_passed || !self.rules.pacing.fallen_after_night)
+    }
+    pub fn locale(&self) -> &Locale {
+        &self.locale
+    }
+    /// Plays the rest of the game in another locale; text without a
+    /// translation keeps the English pronouns
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+    }
+    pub fn sponsor_budget(&self) -> u32 {
+        self.sponsor_budget
+    }
+
+    /// Sends a gift from `rules.sponsors` to the living tribute called `name`
+    ///
+    /// On success the budget is charged, the gift's items and cures take
+    /// effect at once and the parachute drop comes back as a round of its own.
+    pub fn sponsor(&mut self, name: &str, gift_name: &str) -> Result<RoundResult, String> {
+        let strings = &self.locale.strings;
+        let gift = match self.rules.sponsors.gifts.get(gift_name) {
+            Some(gift) => gift,
+            None => return Err(fill(&strings.no_gift, &json!({ "gift": gift_name }))),
+        };
+        let i = match self.roster.find(name) {
+            Some(i) if self.roster.get_tribute(i).alive => i,
+            Some(i) => return Err(fill(&strings.tribute_dead, &json!({ "name": self.roster.get_tribute(i).name }))),
+            None => return Err(fill(&strings.no_tribute, &json!({ "name": name }))),
         };
-        // do nothing with the input for now
-        // next step
+        if gift.cost > self.sponsor_budget {
+            let context = json!({ "gift": gift_name, "cost": gift.cost, "budget": self.sponsor_budget });
+            return Err(fill(&strings.gift_too_costly, &context));
+        }
+        self.sponsor_budget -= gift.cost;
 
-        n_alive = game_roster.n_alive();
-        if n_alive < 2 {
-            break
+        let tb = self.roster.get_tribute_mut(i);
+        for tag in gift.gives.iter() {
+            tb.give_item(tag);
         }
+        for &status in gift.cures.iter() {
+            tb.cure_status(status);
+        }
+        let translated = gift.msg.is_none() && strings.parachute != Strings::default().parachute;
+        let template = gift.msg.as_deref().unwrap_or(&strings.parachute);
+        let tribute = self.roster.serialize_tribute_in(i, translated.then_some(&self.locale));
+        let context = json!({ "0": tribute, "gift": gift_name });
+        let message = render(template, &context);
+        Ok(RoundResult{day:self.day, round_type:RoundType::NONE, name:String::from("sponsor"), event_id:None,
+            title:self.locale.strings.sponsor_title.clone(), members:Vec::new(),
+            actions:vec![ActionResult{id:None, message, members:vec![i], killers:Vec::new(), killed:Vec::new()}]})
+    }
 
-        if night_passed {
-            day += 1;
-            days_since_last_event += 1;
-            day_passed = false;
-            fallen_passed = false;
-            night_passed = false;
+    /// Rolls each status of every living tribute for a death off-screen,
+    /// never leaving fewer than one tribute alive
+    fn offscreen_deaths(&mut self, day: i32) -> Vec<ActionResult> {
+        let mut actions: Vec<ActionResult> = Vec::new();
+        for i in 0..self.roster.len() {
+            if self.roster.n_alive() < 2 {
+                break;
+            }
+            if !self.roster.get_tribute(i).alive {
+                continue;
+            }
+            let statuses = self.roster.get_tribute(i).statuses.clone();
+            for status in statuses.iter() {
+                let chance = status.daily_death_chance();
+                if chance > 0.0 && self.rng.gen_range(0.0, 1.0) < chance {
+                    self.roster.kill(i, day);
+                    let strings = &self.locale.strings;
+                    let translated = strings.status_deaths.contains_key(status);
+                    let context = json!({ "0": self.roster.serialize_tribute_in(i, translated.then_some(&self.locale)) });
+                    actions.push(ActionResult{id:None, message:render(strings.status_death(*status), &context),
+                        members:vec![i], killers:Vec::new(), killed:vec![i]});
+                    break;
+                }
+            }
         }
+        actions
+    }
 
-        let mut feast_chance: f64 = 100.0 * (days_since_last_event as f64).powf(2.0);
-        feast_chance /= 55.0;
-        feast_chance += 9.0 / 55.0;
+    /// Queues the custom rounds that follow a round named `after`
+    fn schedule_custom(&mut self, after: &str) {
+        for (i, custom) in self.events.custom.iter().enumerate() {
+            let schedule = &custom.schedule;
+            if schedule.after != after ||
+                    schedule.min_day.is_some_and(|d| self.day < d) ||
+                    schedule.max_day.is_some_and(|d| self.day > d) ||
+                    schedule.max_per_game.is_some_and(|n| self.custom_played[i] >= n) {
+                continue;
+            }
+            if schedule.chance >= 1.0 || self.rng.gen_range(0.0, 1.0) < schedule.chance {
+                self.pending_custom.push(i);
+                self.custom_played[i] += 1;
+            }
+        }
+    }
+
+    /// Plays the next round, or returns `None` once the game is finished
+    pub fn step(&mut self) -> Option<RoundResult> {
+        let round = self.play_round()?;
+        self.schedule_custom(&round.name);
+        Some(round)
+    }
+
+    fn play_round(&mut self) -> Option<RoundResult> {
+        if self.is_finished() {
+            return None;
+        }
+        let n_alive: i32 = self.roster.n_alive();
+
+        let pacing = &self.rules.pacing;
+        if self.day_over() {
+            self.day += 1;
+            self.days_since_last_event += 1;
+            self.day_passed = false;
+            self.fallen_passed = false;
+            self.night_passed = false;
+            self.night_fallen_passed = false;
+            self.roster.cure_all(Status::Hidden);
+        }
+        let day = self.day;
 
-        let mut fatality_factor: i32 = rng.gen_range(2, 4) + consecutive_rounds_without_deaths;
+        let d = self.days_since_last_event as f64;
+        let feast_chance: f64 = pacing.feast_base + pacing.feast_growth * d * d;
+
+        let mut fatality_factor: i32 = self.rng.gen_range(pacing.fatality_min, pacing.fatality_max + 1) +
+            pacing.drought_bonus * self.consecutive_rounds_without_deaths;
 
         let step_type: RoundType;
-        if (day == 1) && !(bloodbath_passed) {
+        if !self.pending_custom.is_empty() {
+            let i = self.pending_custom.remove(0);
+            step_type = RoundType::CUSTOM(i);
+            fatality_factor += self.events.custom[i].schedule.fatality_bonus;
+        }
+        else if (day == 1) && !(self.bloodbath_passed) {
             step_type = RoundType::BLOODBATH;
-            fatality_factor += 2;
-            bloodbath_passed = true;
+            fatality_factor += pacing.fatality_bonus.bloodbath;
+            self.bloodbath_passed = true;
         }
-        else if !(day_passed) && rng.gen_range(0.0, 100.0) < feast_chance {
+        else if !(self.day_passed) && (pacing.feast_repeats || !self.feast_held) &&
+                self.rng.gen_range(0.0, 100.0) < feast_chance {
             step_type = RoundType::FEAST;
-            days_since_last_event = 0;
-            fatality_factor += 2;
+            self.days_since_last_event = 0;
+            self.feast_held = true;
+            fatality_factor += pacing.fatality_bonus.feast;
         }
-        else if days_since_last_event > 0 && rng.gen_range(1, 20) == 1 {
+        else if self.days_since_last_event > 0 && !self.events.arena.is_empty() &&
+                self.rng.gen_range(1, pacing.arena_odds + 1) == 1 {
             step_type = RoundType::ARENA;
-            days_since_last_event = 0;
-            fatality_factor += 1;
+            self.days_since_last_event = 0;
+            fatality_factor += pacing.fatality_bonus.arena;
         }
-        else if !(day_passed) {
+        else if !(self.day_passed) {
             step_type = RoundType::DAY;
-            day_passed = true;
+            fatality_factor += pacing.fatality_bonus.day;
+            self.day_passed = true;
         }
-        else if day_passed && !(fallen_passed) {
+        else if self.day_passed && !(self.fallen_passed) {
             step_type = RoundType::FALLEN;
-            fallen_passed = true;
+            self.fallen_passed = true;
+        }
+        else if self.night_passed {
+            step_type = RoundType::FALLEN;
+            self.night_fallen_passed = true;
         }
         else {
             step_type = RoundType::NIGHT;
-            night_passed = true;
+            fatality_factor += pacing.fatality_bonus.night;
+            self.night_passed = true;
         }
 
-        let event_key = step_type.as_str();
-
         if step_type == RoundType::FALLEN {
-            let fallen_text = format!("{} cannon shots can be heard from the distance.", game_roster.count_dead_on_day(day));
-            println!("{}", fallen_text);
-            let fallen_members = game_roster.get_dead_indices(day);
-            img::image(fallen_text, game_roster, &fallen_members, &imgidx);
-            imgidx += 1;
-            if game_roster.count_dead_on_day(day) == 0 {
-                consecutive_rounds_without_deaths += 1;
+            // statuses are rolled once a day, at the first FALLEN round
+            let actions = if self.night_fallen_passed { Vec::new() } else { self.offscreen_deaths(day) };
+            let fallen: Vec<usize> = self.roster.get_dead_indices(day).into_iter()
+                .filter(|i| !self.reported_dead.contains(i))
+                .collect();
+            self.reported_dead.extend(fallen.iter());
+            let fallen_text = render(&self.locale.strings.cannon_shots, &json!({ "0": fallen.len() }));
+            if fallen.is_empty() {
+                self.consecutive_rounds_without_deaths += 1;
             }
             else {
-                consecutive_rounds_without_deaths = 0;
-                println!("{}", game_roster.death_summary_on_day(day));
+                self.consecutive_rounds_without_deaths = 0;
             }
-            continue;
+            return Some(RoundResult{day, round_type:step_type, name:step_type.as_str().to_string(), event_id:None,
+                title:fallen_text, members:fallen, actions});
         }
 
-        let event: &Map<String, Value>;
-        // for now just get the event title
-        if step_type == RoundType::ARENA {
-            event = events[event_key]
-                .as_array().unwrap()
-                .choose(&mut rand::thread_rng()).unwrap()
-                .as_object().unwrap();
+        let game_roster = &mut self.roster;
+        let rng = &mut self.rng;
+        let mut arena_idx: usize = 0;
+        let event: &RoundEvents = if step_type == RoundType::ARENA {
+            let arena = &self.events.arena;
+            let indices: Vec<usize> = (0..arena.len()).collect();
+            arena_idx = *indices.choose_weighted(rng, |&i| arena[i].weight).unwrap();
+            &arena[arena_idx]
         }
         else {
-            let event_result = events[event_key].as_object();
-            match event_result {
-                Some(e) => event = e,
-                None => {
-                    println!("no match for events[{}]", event_key);
-                    // pick something that works
-                    event = events["bloodbath"].as_object().unwrap();
-                }
-            }
-        }
+            self.events.round(step_type).unwrap()
+        };
 
-        let mut template = tt.add_template("title_tmp", event["title"].as_str().unwrap());
-        match template {
-            Ok(_) => (),
-            Err(e) => println!("template error.\n {}", e)
-        }
+        self.rounds_played += 1;
+        let round_no = self.rounds_played;
+        let last_used = &mut self.last_used;
 
-        let title_map = json!({
-            "0".to_string(): day
-        });
-        
-        let rendered = tt.render("title_tmp", &title_map);
-
-        match rendered {
-            Ok(r) => {
-                println!("{}", r);
-                img::image(r, game_roster, &vec![], &imgidx);
-                imgidx += 1;
-            }
-            Err(e) => println!("rendering error.\n {}", e)
-        }
+        let locale = &self.locale;
+        let title = render(locale.pick(&event.titles, &event.title), &json!({ "0".to_string(): day }));
 
-        
         game_roster.activate();
-        let mut action_members: Vec<usize> = Vec::with_capacity(game_roster.n_available() as usize);
+        let mut actions: Vec<ActionResult> = Vec::new();
+        let mut failed_draws: u32 = 0;
 
         while game_roster.n_available() > 0 {
+            if failed_draws == MAX_FAILED_DRAWS {
+                // no action fits the tributes left, they sit this round out
+                break;
+            }
+            failed_draws += 1;
+            let alive_now = game_roster.n_alive() as usize;
             let f: i32 = rng.gen_range(0, 10);
-            let action: &Value;
-            action_members.clear();
-
-
-            if f < fatality_factor && n_alive > 1 {
-                // time to die
-                action = event["fatal"].as_array().unwrap()
-                    .choose(&mut rand::thread_rng()).unwrap();
-                if action["killed"].as_array().unwrap().len() >= n_alive as usize {
-                    // not enough alive to satisfy event
-                    continue;
-                }
+            let fatal_pick = if f < fatality_factor && n_alive > 1 && !event.fatal.is_empty() {
+                // time to die, unless every fatal action is used up, ruled
+                // out by its conditions or kills too many
+                let usable = |i, a: &Action| a.killed.len() < alive_now && a.when.allow(day, alive_now) &&
+                    can_reuse(a, last_used.get(&(step_type, arena_idx, true, i)), round_no);
+                pick_action(&event.fatal, usable, rng)
             }
             else {
-                action = event["nonfatal"].as_array().unwrap()
-                    .choose(&mut rand::thread_rng()).unwrap();
-            }
+                None
+            };
+            let fatal = fatal_pick.is_some();
+            let (action_idx, action) = match fatal_pick {
+                Some(pick) => pick,
+                None => {
+                    let usable = |i, a: &Action| a.when.allow(day, alive_now) &&
+                        can_reuse(a, last_used.get(&(step_type, arena_idx, false, i)), round_no);
+                    match pick_action(&event.nonfatal, usable, rng) {
+                        Some(pick) => pick,
+                        // every nonfatal action is used up or ruled out by
+                        // its conditions
+                        None => continue
+                    }
+                }
+            };
 
-            let mut action_tributes: i64 = action["tributes"].as_i64().unwrap();
-            let action_number = action_tributes as usize;
-            if action_tributes > game_roster.n_available().into() {
+            if action.tributes > game_roster.n_available() as usize {
                 // not enough available to satisfy event
                 continue;
             }
 
-            // UNSAFE, need force exit from loop
-            while action_tributes > 0 {
-                let idx: usize = rng.gen_range(0, game_roster.len());
-                if game_roster.get_available(idx) {
-                    action_members.push(idx);
-                    game_roster.set_unavailable(idx);
-                    action_tributes -= 1;
-                }
+            let action_members = match pick_members(game_roster, action, rng) {
+                Some(members) => members,
+                // nobody carries what the action needs
+                None => continue
+            };
+            for &m in action_members.iter() {
+                game_roster.set_unavailable(m);
             }
 
-            //println!("ACTION MEMBERS");
-            //for v in action_members.iter() {
-            //    println!("{}", v);
-            //}
-
-            match action["killed"].as_array() {
-                Some(killed_array) => {
-                    match action["killer"].as_array() {
-                        Some(killer_array) => {
-                            for kr in killer_array {
-                                let kr_res = kr.as_u64();
-                                match kr_res {
-                                    Some(_) => game_roster.add_kill(action_members[kr.as_u64().unwrap() as usize]),
-                                    None => () // no killer
-                                }
-                            }
-                        }
-                        None => ()
-                    }
-                    for kd in killed_array {
-                        game_roster.kill(action_members[kd.as_u64().unwrap() as usize], day)
+            let killers: Vec<usize> = action.killer.iter().map(|&k| action_members[k]).collect();
+            let killed: Vec<usize> = action.killed.iter().map(|&k| action_members[k]).collect();
+
+            let partners_clash = killers.iter()
+                .any(|&kr| killed.iter().any(|&kd| game_roster.same_district(kr, kd)));
+            if partners_clash && rng.gen_range(0.0, 1.0) < self.rules.district_loyalty {
+                // district partners spare each other, draw again
+                for &m in action_members.iter() {
+                    game_roster.set_available(m);
+                }
+                continue;
+            }
+            for &kr in killers.iter() {
+                game_roster.add_kill(kr);
+            }
+            for &kd in killed.iter() {
+                game_roster.kill(kd, day);
+            }
+            if action.betray {
+                for &a in action_members.iter() {
+                    for &b in action_members.iter() {
+                        game_roster.break_alliance(a, b);
                     }
                 }
-                None => ()
             }
-
-            template = tt.add_template("msg_tmp", action["msg"].as_str().unwrap());
-            match template {
-                Ok(_) => (),
-                Err(e) => println!("template error.\n {}", e)
+            for &a in action.alliance.iter() {
+                for &b in action.alliance.iter() {
+                    game_roster.ally(action_members[a], action_members[b]);
+                }
+            }
+            for (&slot, tags) in action.consumes.iter() {
+                for tag in tags.iter() {
+                    game_roster.get_tribute_mut(action_members[slot]).take_item(tag);
+                }
+            }
+            for (&slot, tags) in action.gives.iter() {
+                for tag in tags.iter() {
+                    game_roster.get_tribute_mut(action_members[slot]).give_item(tag);
+                }
+            }
+            for (&slot, statuses) in action.cures.iter() {
+                for &status in statuses.iter() {
+                    game_roster.get_tribute_mut(action_members[slot]).cure_status(status);
+                }
+            }
+            for (&slot, statuses) in action.applies.iter() {
+                let tb = game_roster.get_tribute_mut(action_members[slot]);
+                if tb.alive {
+                    for &status in statuses.iter() {
+                        tb.add_status(status);
+                    }
+                }
             }
 
-            // println!("action number: {}", action_number);
-            // println!("message: {}", action["msg"].as_str().unwrap());
+            last_used.insert((step_type, arena_idx, fatal, action_idx), round_no);
 
-            let context_map: Map<String, Value> = (0..action_number)
-                .map(|i| (i.to_string(), game_roster.serialize_tribute(action_members[i]).into()))
+            // translations use the locale's pronouns, the English `msg` its own
+            let translation = locale.translation(&action.msgs);
+            let pronouns = translation.map(|_| locale);
+            let context_map: Map<String, Value> = action_members.iter().enumerate()
+                .map(|(i, &m)| (i.to_string(), game_roster.serialize_tribute_in(m, pronouns)))
                 .collect::<Map<String, Value>>();
 
-            // println!("CONTEXT MAP");
-            // for (key, val) in context_map.iter() {
-            //    println!("{}: {}", key, val)
-            // }
+            let message = render(translation.unwrap_or(&action.msg), &context_map);
+
+            actions.push(ActionResult{id:action.id.clone(), message, members:action_members, killers, killed});
+            failed_draws = 0;
+        }
 
-            let rendered = tt.render("msg_tmp", &context_map);
+        let name = self.events.round_name(step_type).to_string();
+        Some(RoundResult{day, round_type:step_type, name, event_id:event.id.clone(), title, members:Vec::new(), actions})
+    }
+}
 
-            match rendered {
-                Ok(r) => {
-                    println!("{}", r);
-                    img::image(r, game_roster, &action_members, &imgidx);
+/// Reads sponsor commands until an empty line, handing each gift to the
+/// outputs as it lands
+fn sponsor_pause(sim: &mut Simulation, outputs: &mut [Box<dyn Output>]) {
+    loop {
+        let mut input = String::new();
+        match io::stdin().read_line(&mut input) {
+            Ok(0) => return,
+            Ok(_) => {},
+            Err(error) => {
+                println!("error: {}", error);
+                return;
+            }
+        };
+        let words: Vec<&str> = input.split_whitespace().collect();
+        match words.first() {
+            None => return,
+            Some(&"sponsor") if words.len() >= 3 => {
+                let name = words[1..words.len() - 1].join(" ");
+                match sim.sponsor(&name, words[words.len() - 1]) {
+                    Ok(round) => {
+                        for output in outputs.iter_mut() {
+                            output.round(sim, &round);
+                        }
+                        println!("{}", render(&sim.locale.strings.budget_left, &json!({ "0": sim.sponsor_budget() })));
+                    }
+                    Err(e) => println!("{}", e),
                 }
-                Err(e) => println!("rendering error.\n {}", e)
             }
+            Some(_) => println!("{}", sim.locale.strings.sponsor_usage),
+        }
+    }
+}
+
+/// Front end: plays the game to completion, handing every round to each
+/// output in turn
+///
+/// With `pause` set it waits for enter on stdin before every round, taking
+/// `sponsor <tribute> <gift>` commands in the meantime.
+pub fn gameloop(sim: &mut Simulation, outputs: &mut [Box<dyn Output>], pause: bool) -> i32 {
+    let status: i32 = 0;
 
-            imgidx += 1;       
-            
-            // process::exit(0x0000)
+    for output in outputs.iter_mut() {
+        output.start(sim);
+    }
+    if pause {
+        let gifts: Vec<String> = sim.rules.sponsors.gifts.iter()
+            .map(|(name, gift)| format!("{} ({})", name, gift.cost))
+            .collect();
+        let context = json!({ "budget": sim.sponsor_budget(), "gifts": gifts.join(", ") });
+        println!("{}", render(&sim.locale.strings.sponsor_help, &context));
+    }
 
+    loop {
+        if pause {
+            sponsor_pause(sim, outputs);
         }
 
+        let round = match sim.step() {
+            Some(round) => round,
+            None => break
+        };
+
+        for output in outputs.iter_mut() {
+            output.round(sim, &round);
+        }
     }
 
-    // Simulation complete, print details
-    println!("{}", game_roster.game_summary());
+    // Simulation complete
+    for output in outputs.iter_mut() {
+        output.finish(sim);
+    }
 
     status
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A pack that plays the same round in every fixed round type
+    fn events(nonfatal: Value, fatal: Value) -> EventSet {
+        let round = json!({ "title": "Day {0}", "nonfatal": nonfatal, "fatal": fatal });
+        EventSet::from_value(&json!({ "bloodbath": round, "day": round, "night": round, "feast": round })).unwrap()
+    }
+
+    fn tributes(n: usize) -> roster::Roster {
+        let entries: Vec<Value> = (0..n).map(|i| json!({ "name": format!("T{}", i) })).collect();
+        roster::Roster::from_value(&Value::Array(entries)).unwrap()
+    }
+
+    /// Plays a game to the end, failing if it goes on for far too long
+    fn play(events: EventSet, n: usize, seed: u64) -> Vec<RoundResult> {
+        let mut sim = Simulation::new(tributes(n), events, Rules::default(), seed);
+        let mut rounds: Vec<RoundResult> = Vec::new();
+        while let Some(round) = sim.step() {
+            rounds.push(round);
+            assert!(rounds.len() < 10_000, "the game never ends");
+        }
+        assert!(sim.is_finished());
+        rounds
+    }
+
+    /// Plays a game on the bundled roster and event pack
+    fn bundled_game(seed: u64) -> Vec<RoundResult> {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("data");
+        let game_roster = roster::Roster::load(&dir.join("roster.json")).unwrap();
+        let events = EventSet::load(&dir.join("events.json")).unwrap();
+        let mut sim = Simulation::new(game_roster, events, Rules::default(), seed);
+        std::iter::from_fn(|| sim.step()).collect()
+    }
+
+    #[test]
+    fn same_seed_replays_the_same_game() {
+        for seed in 0..20 {
+            assert_eq!(bundled_game(seed), bundled_game(seed));
+        }
+        assert!((0..20).any(|seed| bundled_game(seed) != bundled_game(seed + 1)));
+    }
+
+    #[test]
+    fn round_ends_when_no_action_fits_the_tributes_left() {
+        let events = events(
+            json!([
+                { "msg": "{0.name} shoots.", "tributes": 1, "requires": { "0": ["bow"] } },
+                { "msg": "{0.name} and {1.name} talk.", "tributes": 2 },
+            ]),
+            json!([{ "msg": "{0.name} kills {1.name}.", "tributes": 2, "killer": [0], "killed": [1] }]));
+        for seed in 0..5 {
+            play(events.clone(), 3, seed);
+        }
+    }
+
+    #[test]
+    fn used_up_fatal_actions_fall_back_to_nonfatal_ones() {
+        let events = events(
+            json!([{ "msg": "{0.name} rests.", "tributes": 1 }]),
+            json!([{ "msg": "{0.name} kills {1.name}.", "tributes": 2, "killer": [0], "killed": [1],
+                "once_per_game": true }]));
+        // four fatal actions in the whole game, one per round type, so the
+        // game runs until `max_days`
+        let rounds = play(events, 10, 1);
+        let deaths: usize = rounds.iter().flat_map(|r| r.actions.iter()).map(|a| a.killed.len()).sum();
+        assert!(deaths <= 4);
+        assert_eq!(rounds.last().unwrap().day, Rules::default().max_days);
+    }
+
+    #[test]
+    fn conditions_ruling_out_every_fatal_action_do_not_stall() {
+        let events = events(
+            json!([{ "msg": "{0.name} rests.", "tributes": 1 }]),
+            json!([{ "msg": "{0.name} kills {1.name}.", "tributes": 2, "killer": [0], "killed": [1],
+                "when": { "max_alive": 3 } }]));
+        let rounds = play(events, 10, 1);
+        assert!(rounds.iter().all(|r| r.actions.iter().all(|a| a.killed.is_empty())));
+    }
+
+    #[test]
+    fn sponsor_charges_the_budget_and_helps_the_tribute() {
+        let events = events(json!([{ "msg": "{0.name} rests.", "tributes": 1 }]), json!([]));
+        let mut sim = Simulation::new(tributes(3), events, Rules::default(), 1);
+        sim.roster.get_tribute_mut(1).add_status(Status::Wounded);
+        sim.roster.kill(2, 0);
+
+        let round = sim.sponsor("t1", "medicine").unwrap();
+        assert_eq!(sim.sponsor_budget(), 60);
+        let tb = sim.roster().get_tribute(1);
+        assert!(tb.has_items(&["medicine"]));
+        assert!(!tb.has_status(Status::Wounded));
+        assert_eq!(round.actions[0].members, vec![1]);
+        assert_eq!(round.actions[0].message, "A silver parachute drifts down to T1, carrying medicine.");
+
+        sim.sponsor("T1", "bow").unwrap();
+        assert_eq!(sim.sponsor_budget(), 10);
+        assert_eq!(sim.sponsor("T1", "food").unwrap_err(), "food costs 20, only 10 left");
+        assert!(!sim.roster().get_tribute(1).has_items(&["food"]));
+        assert_eq!(sim.sponsor("T2", "food").unwrap_err(), "T2 is dead");
+        assert!(sim.sponsor("T0", "cake").is_err());
+        assert!(sim.sponsor("T9", "food").is_err());
+        assert_eq!(sim.sponsor_budget(), 10);
+    }
+
+    #[test]
+    fn only_translated_text_uses_the_locale_pronouns() {
+        let events = events(
+            json!([
+                { "msg": "{0.name} packs {0.genG} bag.", "tributes": 1 },
+                { "msg": "{0.name} packs {0.genG} bag again.", "msgs": { "de": "{0.name} packt {0.genG}en Rucksack." },
+                    "tributes": 1 },
+            ]),
+            json!([]));
+        let game_roster = roster::Roster::from_value(&json!([
+            { "name": "Dan", "gender": "M" }, { "name": "John", "gender": "M" }])).unwrap();
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("data").join("locales");
+        let mut sim = Simulation::new(game_roster, events, Rules::default(), 1);
+        sim.set_locale(Locale::load(&dir, "de").unwrap());
+        let messages: Vec<String> = std::iter::from_fn(|| sim.step()).take(20)
+            .flat_map(|r| r.actions.into_iter().map(|a| a.message))
+            .collect();
+        assert!(messages.iter().any(|m| m.ends_with("packs his bag.")));
+        assert!(messages.iter().any(|m| m.ends_with("packt seinen Rucksack.")));
+        assert!(messages.iter().all(|m| m.ends_with("packs his bag.") || m.ends_with("packt seinen Rucksack.")),
+            "{:?}", messages);
+    }
+
+    #[test]
+    fn used_up_nonfatal_actions_are_not_played_again() {
+        let events = events(
+            json!([
+                { "msg": "{0.name} finds a bow.", "tributes": 1, "once_per_game": true },
+                { "msg": "{0.name} naps.", "tributes": 1, "cooldown": 2 },
+            ]),
+            json!([{ "msg": "{0.name} kills {1.name}.", "tributes": 2, "killer": [0], "killed": [1] }]));
+        for seed in 0..5 {
+            let rounds = play(events.clone(), 6, seed);
+            // each round type has its own copy of the actions
+            for &round_type in [RoundType::BLOODBATH, RoundType::DAY, RoundType::NIGHT, RoundType::FEAST].iter() {
+                let played = |msg: &str| -> Vec<usize> {
+                    rounds.iter().enumerate()
+                        .filter(|(_, r)| r.round_type == round_type)
+                        .flat_map(|(i, r)| r.actions.iter().filter(|a| a.message.ends_with(msg)).map(move |_| i))
+                        .collect()
+                };
+                assert!(played("finds a bow.").len() <= 1);
+                let naps = played("naps.");
+                assert!(naps.windows(2).all(|w| w[1] - w[0] > 2), "naps in rounds {:?}", naps);
+            }
+        }
+    }
+
+    #[test]
+    fn nonfatal_actions_are_never_played_against_their_conditions() {
+        let events = events(
+            json!([{ "msg": "{0.name} gives a final speech.", "tributes": 1, "when": { "final": 2 } }]),
+            json!([{ "msg": "{0.name} kills {1.name}.", "tributes": 2, "killer": [0], "killed": [1] }]));
+        for seed in 0..5 {
+            let mut deaths = 0;
+            for action in play(events.clone(), 5, seed).iter().flat_map(|r| r.actions.iter()) {
+                if action.killed.is_empty() {
+                    assert_eq!(deaths, 3, "speech with {} tributes left", 5 - deaths);
+                }
+                deaths += action.killed.len();
+            }
+        }
+    }
+}
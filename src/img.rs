@@ -26,20 +26,46 @@ static AVATAR_PADDING: u32 = 32;
 static GLYPH_PADDING: u32 = 64;
 static MAX_IMAGE_WIDTH: u32 = 1200;
 
-pub fn get_current_dir() -> PathBuf {
-    std::env::current_dir().ok().expect("Something went wrong trying to read in the current directory (check permissions)")
+/// Where rendering reads avatars and the font from, and writes images to
+#[derive(Clone, Debug)]
+pub struct ImagePaths {
+    pub font: PathBuf,
+    pub avatars_dir: PathBuf,
+    pub out_dir: PathBuf,
 }
 
-pub fn init_thumbs(roster: &roster::Roster) {
+impl Default for ImagePaths {
+    /// The historical layout, relative to the current directory
+    fn default() -> ImagePaths {
+        ImagePaths{font:PathBuf::from("fonts/Roboto-Regular.ttf"),
+            avatars_dir:PathBuf::from("input"), out_dir:PathBuf::from("output")}
+    }
+}
+
+impl ImagePaths {
+    pub fn thumbs_dir(&self) -> PathBuf {
+        self.out_dir.join("thumbs")
+    }
+    fn thumb(&self, image_name: &str) -> PathBuf {
+        let truncated_image_name: &str = match Path::new(image_name).file_stem() {
+            Some(stem) => stem.to_str().expect("Path to string conversion failed!"),
+            None => image_name
+        };
+        self.thumbs_dir().join(format!("{}.png", truncated_image_name))
+    }
+}
+
+pub fn init_thumbs(roster: &roster::Roster, paths: &ImagePaths) {
     for i in 0..roster.len() {
-        let image_name = roster.get_avatar(i).expect("Failed to read image name");
+        let image_name = match roster.get_avatar(i) {
+            Some(name) => name,
+            // no avatar, a blank square is drawn instead
+            None => continue
+        };
         println!("saving {} as thumbnail...", image_name);
         let avatar_reader = match roster.get_avatar(i) {
             Some(avatar_path) => {
-                let mut input_path: PathBuf = get_current_dir();
-                input_path.push("input");
-                input_path.push(Path::new(&avatar_path));
-                match Reader::open(input_path) {
+                match Reader::open(paths.avatars_dir.join(&avatar_path)) {
                     Ok(result) => Some(result),
                     Err(error) => {
                         println!("error: {}", error);
@@ -63,29 +89,21 @@ pub fn init_thumbs(roster: &roster::Roster) {
             None => DynamicImage::new_rgba8(AVATAR_DIMENSION, AVATAR_DIMENSION)
         };
 
-        match fs::create_dir("thumbs") {
-            Ok(_) => (),
-            Err(_) => ()
-        };
+        let _ = fs::create_dir_all(paths.thumbs_dir());
 
-        let truncated_image_name: &str = match Path::new(&image_name).file_stem() {
-            Some(stem) => stem.to_str().expect("Path to string conversion failed!"),
-            None => &image_name
-        };
-        let save_str: String = format!("thumbs/{}.png", truncated_image_name);
-        let save_path: &Path = Path::new(&save_str);
+        let save_path: PathBuf = paths.thumb(&image_name);
         if save_path.exists() {
             continue;
         }
-        match avatar.save(save_path) {
+        match avatar.save(&save_path) {
             Ok(_) => (),
             Err(_) => println!("Saving the thumbnail failed!")
         };
     };
 }
 
-pub fn get_thumb(avatar_path: String) -> DynamicImage {
-    let avatar_reader = match Reader::open(format!("{}", avatar_path)) {
+pub fn get_thumb(avatar_path: &Path) -> DynamicImage {
+    let avatar_reader = match Reader::open(avatar_path) {
         Ok(result) => Some(result),
         Err(error) => {
             println!("error: {}", error);
@@ -107,13 +125,9 @@ pub fn get_thumb(avatar_path: String) -> DynamicImage {
     avatar
 }
 
-pub fn image(text_input: String, game_roster: &roster::Roster, action_members: &Vec<usize>, idx: &u32) {
+pub fn image(text_input: String, game_roster: &roster::Roster, action_members: &[usize], idx: &u32, paths: &ImagePaths) {
     // Load the font
-    let mut font_path: PathBuf = get_current_dir();
-    font_path.push("fonts");
-    // TODO give user power to change this
-    font_path.push("Roboto-Regular.ttf");
-    let font_data = fs::read(font_path).expect("Error reading font data");
+    let font_data = fs::read(&paths.font).expect("Error reading font data");
     // This only succeeds if collection consists of one font
     let font = Font::try_from_vec(font_data).expect("Error constructing Font");
 
@@ -152,7 +166,7 @@ pub fn image(text_input: String, game_roster: &roster::Roster, action_members: &
     let number_columns: u32 = cmp::min(action_members.len() as u32, number_avatar_horizontal);
 
     let avatar_block_width: u32 = (AVATAR_DIMENSION + AVATAR_PADDING) * number_columns + AVATAR_PADDING;
-    let avatar_block_height: u32 = if action_members.len() == 0 {
+    let avatar_block_height: u32 = if action_members.is_empty() {
         GLYPH_PADDING
     } else {
         (AVATAR_DIMENSION + AVATAR_PADDING) * number_rows + AVATAR_PADDING
@@ -185,22 +199,15 @@ pub fn image(text_input: String, game_roster: &roster::Roster, action_members: &
 
     // Load in image from file
     for (i, a) in action_members.iter().enumerate() {
-        let image_name = game_roster.get_avatar(*a).expect("Failed to read image name");
-        let truncated_image_name: &str = match Path::new(&image_name).file_stem() {
-            Some(stem) => stem.to_str().expect("Path to string conversion failed!"),
-            None => &image_name
+        let avatar = match game_roster.get_avatar(*a) {
+            Some(image_name) => get_thumb(&paths.thumb(&image_name)),
+            None => DynamicImage::new_rgba8(AVATAR_DIMENSION, AVATAR_DIMENSION)
         };
-        let image_file_str: String = format!("thumbs/{}.png", truncated_image_name);
-        let avatar = get_thumb(image_file_str);
         let px: u32 = (i as u32) % number_columns;
         let py: u32 = (i as u32) / number_columns;
-        println!("{}, {}", px, py);
-        match fullimage.copy_from(&avatar,
+        let _ = fullimage.copy_from(&avatar,
                 avatar_block_left + (AVATAR_PADDING + AVATAR_DIMENSION) * px, 
-                AVATAR_PADDING + (AVATAR_PADDING + AVATAR_DIMENSION) * py) {
-            Ok(_) => (),
-            Err(_) => ()
-        };
+                AVATAR_PADDING + (AVATAR_PADDING + AVATAR_DIMENSION) * py);
     }
 
     // Loop through the glyphs in the text, positing each one on a line
@@ -234,13 +241,7 @@ pub fn image(text_input: String, game_roster: &roster::Roster, action_members: &
     //}
 
     // Save the image to a png file
-    match fs::create_dir("output") {
-        Ok(_) => (),
-        Err(_) => ()
-    };
-    let mut output_path: PathBuf = get_current_dir();
+    let _ = fs::create_dir_all(&paths.out_dir);
     let fullimage_name: String = format!("hg{:03}.png", idx);
-    output_path.push("output");
-    output_path.push(Path::new(fullimage_name.as_str()));
-    fullimage.save(output_path).unwrap();
+    fullimage.save(paths.out_dir.join(fullimage_name)).unwrap();
 }
@@ -0,0 +1,22 @@
+//! Hunger Game Simulator library
+//!
+//! Build a `Simulation` from a `Roster` and an event pack, then call `step`
+//! to play it one round at a time without any console or image I/O.
+
+extern crate rand;
+
+pub mod tribute;
+pub mod roster;
+pub mod game;
+pub mod img;
+pub mod events;
+pub mod lint;
+pub mod log;
+pub mod output;
+pub mod settings;
+pub mod stats;
+pub mod locale;
+
+pub use game::{Simulation, RoundResult, ActionResult, RoundType};
+pub use roster::Roster;
+pub use events::EventSet;
@@ -0,0 +1,870 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use serde_json::Value;
+use tinytemplate::TinyTemplate;
+
+use super::events;
+use super::tribute;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match &self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// A single finding from the event pack linter
+#[derive(Clone, Debug)]
+pub struct LintIssue {
+    pub severity: Severity,
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.location, self.severity.as_str(), self.message)
+    }
+}
+
+/// Placeholder found in a template: `{1.name}` gives `Some(1)` and `"name"`
+struct Placeholder {
+    index: Option<usize>,
+    field: String,
+    raw: String,
+    /// byte offsets of the opening and closing braces
+    start: usize,
+    end: usize,
+}
+
+/// Pulls every `{...}` placeholder out of a TinyTemplate string
+fn placeholders(template: &str) -> Vec<Placeholder> {
+    let mut found: Vec<Placeholder> = Vec::new();
+    let mut chars = template.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c == '\\' {
+            // escaped character, skip it
+            chars.next();
+            continue;
+        }
+        if c != '{' {
+            continue;
+        }
+        let mut end = None;
+        for (i, c) in chars.by_ref() {
+            if c == '}' {
+                end = Some(i);
+                break;
+            }
+        }
+        let end = match end {
+            Some(end) => end,
+            None => break,
+        };
+        let raw = template[start + 1..end].trim().to_string();
+        let mut parts = raw.splitn(2, '.');
+        let head = parts.next().unwrap_or("");
+        let field = parts.next().unwrap_or("").to_string();
+        found.push(Placeholder{index:head.parse::<usize>().ok(), field, raw, start, end});
+    }
+    found
+}
+
+struct Linter {
+    issues: Vec<LintIssue>,
+    /// item tags some action gives
+    given: HashSet<String>,
+    /// item tag -> first location that requires or consumes it
+    needed: BTreeMap<String, String>,
+    /// action or arena event id -> the pack number, list and location it was
+    /// first seen at, the list being e.g. `day.fatal` or `arena`
+    ids: HashMap<String, (usize, String, String)>,
+    /// custom round names from this and earlier packs
+    custom_names: Vec<String>,
+    /// number of the pack being checked, 0 for the base pack
+    pack_no: usize,
+    /// file name put in front of every location when linting several packs
+    pack: String,
+}
+
+impl Linter {
+    fn locate(&self, location: &str) -> String {
+        match (self.pack.is_empty(), location.is_empty()) {
+            (true, _) => location.to_string(),
+            (false, true) => self.pack.clone(),
+            (false, false) => format!("{}: {}", self.pack, location),
+        }
+    }
+    fn error(&mut self, location: &str, message: String) {
+        self.issues.push(LintIssue{severity:Severity::Error, location:self.locate(location), message});
+    }
+    fn warning(&mut self, location: &str, message: String) {
+        self.issues.push(LintIssue{severity:Severity::Warning, location:self.locate(location), message});
+    }
+
+    fn check_indices(&mut self, location: &str, action: &Value, field: &str, tributes: Option<u64>) -> usize {
+        let list = match action.get(field) {
+            Some(Value::Array(list)) => list,
+            Some(_) => {
+                self.error(location, format!("`{}` must be an array of tribute indices", field));
+                return 0;
+            }
+            None => return 0,
+        };
+        for v in list.iter() {
+            match (v.as_u64(), tributes) {
+                (Some(idx), Some(n)) if idx >= n => {
+                    self.error(location, format!("`{}` index {} is out of range for {} tributes", field, idx, n));
+                }
+                (Some(_), _) => (),
+                (None, _) => self.error(location, format!("`{}` contains {}, expected a tribute index", field, v)),
+            }
+        }
+        list.len()
+    }
+
+    /// Checks an object keyed by tribute index, e.g. `{"0": ...}`, returning its values
+    fn check_index_map<'v>(&mut self, location: &str, map: Option<&'v Value>, field: &str, tributes: Option<u64>) -> Vec<(String, &'v Value)> {
+        let map = match map {
+            Some(Value::Object(map)) => map,
+            Some(_) => {
+                self.error(location, format!("`{}` must be an object keyed by tribute index", field));
+                return Vec::new();
+            }
+            None => return Vec::new(),
+        };
+        for key in map.keys() {
+            match (key.parse::<u64>(), tributes) {
+                (Ok(idx), Some(n)) if idx >= n => {
+                    self.error(location, format!("`{}` index {} is out of range for {} tributes", field, idx, n));
+                }
+                (Ok(_), _) => (),
+                (Err(_), _) => self.error(location, format!("`{}` key `{}` is not a tribute index", field, key)),
+            }
+        }
+        map.iter().map(|(key, v)| (key.clone(), v)).collect()
+    }
+
+    /// Checks a map of tribute index -> list of tags (`what` being e.g. "item tag")
+    fn check_tags(&mut self, location: &str, map: Option<&Value>, field: &str, what: &str, tributes: Option<u64>) -> Vec<String> {
+        let mut found: Vec<String> = Vec::new();
+        for (key, tags) in self.check_index_map(location, map, field, tributes) {
+            let tags = match tags.as_array() {
+                Some(tags) => tags,
+                None => {
+                    self.error(location, format!("`{}.{}` must be a list of {}s", field, key, what));
+                    continue;
+                }
+            };
+            for tag in tags.iter() {
+                match tag.as_str() {
+                    Some(tag) if !tag.trim().is_empty() => found.push(tag.to_string()),
+                    _ => self.error(location, format!("`{}.{}` contains {}, expected a {}", field, key, tag, what)),
+                }
+            }
+        }
+        found
+    }
+
+    fn check_weight(&mut self, location: &str, weight: Option<&Value>) {
+        if let Some(weight) = weight {
+            if !weight.as_f64().is_some_and(|w| w > 0.0) {
+                self.error(location, format!("`weight` must be a positive number, found {}", weight));
+            }
+        }
+    }
+
+    fn check_when(&mut self, location: &str, when: &Value, tributes: Option<u64>) {
+        let when = match when.as_object() {
+            Some(when) => when,
+            None => {
+                self.error(location, format!("`when` must be an object, found {}", when));
+                return;
+            }
+        };
+        for (key, v) in when.iter() {
+            match key.as_str() {
+                "min_day" | "max_day" | "min_alive" | "max_alive" | "final" => {
+                    if v.as_u64().is_none() {
+                        self.error(location, format!("`when.{}` must be a non-negative integer, found {}", key, v));
+                    }
+                }
+                "min_kills" => {
+                    for (key, k) in self.check_index_map(location, Some(v), "when.min_kills", tributes) {
+                        if k.as_u64().is_none() {
+                            self.error(location, format!("`when.min_kills.{}` must be a kill count, found {}", key, k));
+                        }
+                    }
+                }
+                "status" => {
+                    for status in self.check_tags(location, Some(v), "when.status", "status", tributes) {
+                        if status.parse::<tribute::Status>().is_err() {
+                            self.error(location, format!("`when.status` names unknown status `{}` (expected one of {})",
+                                status, tribute::STATUS_NAMES.join("/")));
+                        }
+                    }
+                }
+                _ => self.error(location, format!("unknown condition `when.{}`", key)),
+            }
+        }
+        for field in ["day", "alive"].iter() {
+            let min = when.get(&format!("min_{}", field)).and_then(|v| v.as_u64());
+            let max = when.get(&format!("max_{}", field)).and_then(|v| v.as_u64());
+            if let (Some(min), Some(max)) = (min, max) {
+                if min > max {
+                    self.error(location, format!("`when.min_{}` {} is above `when.max_{}` {}, the action can never happen",
+                        field, min, field, max));
+                }
+            }
+        }
+        if let (Some(n), Some(t)) = (when.get("final").and_then(|v| v.as_u64()), tributes) {
+            if n < t {
+                self.error(location, format!("`when.final` {} leaves too few tributes for this action", n));
+            }
+        }
+    }
+
+    /// `english` turns on the verb agreement checks, which only make sense
+    /// for English messages; translations may not use the verb fields at all
+    fn check_template(&mut self, location: &str, msg: &str, tributes: Option<u64>, english: bool) {
+        let mut tt = TinyTemplate::new();
+        if let Err(e) = tt.add_template("lint", msg) {
+            self.error(location, format!("template does not compile: {}", e));
+            return;
+        }
+
+        let mut mentioned: Vec<bool> = vec![false; tributes.unwrap_or(0) as usize];
+        for p in placeholders(msg).iter() {
+            let idx = match p.index {
+                Some(idx) => idx,
+                None => {
+                    self.error(location, format!("placeholder `{{{}}}` does not name a tribute index", p.raw));
+                    continue;
+                }
+            };
+            if let Some(n) = tributes {
+                if idx as u64 >= n {
+                    self.error(location, format!("placeholder `{{{}}}` refers to tribute {} but `tributes` is {}", p.raw, idx, n));
+                }
+                else {
+                    mentioned[idx] = true;
+                }
+            }
+            if !tribute::TEMPLATE_FIELDS.contains(&p.field.as_str()) {
+                self.error(location, format!("placeholder `{{{}}}` uses unknown field `{}` (expected one of {})",
+                    p.raw, p.field, tribute::TEMPLATE_FIELDS.join("/")));
+            }
+            if !english && tribute::VERB_FIELDS.contains(&p.field.as_str()) {
+                self.error(location, format!("placeholder `{{{}}}` only renders English verb forms, write the verb out",
+                    p.raw));
+            }
+            self.check_grammar(location, msg, p, idx, english);
+        }
+        for (idx, m) in mentioned.iter().enumerate() {
+            if !m {
+                self.warning(location, format!("tribute {} takes part but is never mentioned", idx));
+            }
+        }
+    }
+
+    /// Warns about pronouns that start a sentence in lowercase, and about
+    /// verbs after `genN` that only agree with singular pronouns
+    fn check_grammar(&mut self, location: &str, msg: &str, p: &Placeholder, idx: usize, english: bool) {
+        if !tribute::PRONOUN_FIELDS.contains(&p.field.as_str()) {
+            return;
+        }
+        let before = msg[..p.start].trim_end();
+        if before.is_empty() || before.ends_with(['.', '!', '?']) {
+            self.warning(location, format!("`{{{}}}` starts a sentence, use `{{{}.G{}}}`",
+                p.raw, idx, &p.field[1..]));
+        }
+        if !english || p.field != "genN" {
+            return;
+        }
+        let verb: String = msg[p.end + 1..].trim_start().chars()
+            .take_while(|c| c.is_alphabetic() || *c == '\'')
+            .collect();
+        let suggestion = match verb.as_str() {
+            "is" | "was" | "has" | "does" => format!("{{{}.{}}}", idx, verb),
+            "isn't" | "wasn't" | "hasn't" | "doesn't" => format!("{{{}.{}}}n't", idx, &verb[..verb.len() - 3]),
+            v if v.len() > 4 && v.ends_with("ies") => format!("{}{{{}.ies}}", &v[..v.len() - 3], idx),
+            v if v.ends_with('s') && !v.ends_with("ss") => format!("{}{{{}.s}}", &v[..v.len() - 1], idx),
+            _ => return,
+        };
+        self.warning(location, format!("`{{{}}} {}` reads wrong for plural pronouns, use `{}`",
+            p.raw, verb, suggestion));
+    }
+
+    /// Checks an action or arena event id; a later pack may reuse an id to
+    /// replace an action of the same round and kind, or an arena event, but
+    /// ids never repeat within one pack
+    fn check_id(&mut self, location: &str, id: Option<&Value>) {
+        match id {
+            Some(Value::String(id)) if id.trim().is_empty() => self.error(location, String::from("`id` is empty")),
+            Some(Value::String(id)) => {
+                let mut list = location[..location.rfind('[').unwrap_or(location.len())].to_string();
+                if let (true, Some(end)) = (list.starts_with("arena["), list.find(']')) {
+                    // an arena event's actions go with it when a later pack
+                    // replaces the event, wherever either sits in its pack
+                    list.replace_range(6..end, "");
+                }
+                let at = self.locate(location);
+                match self.ids.get(id) {
+                    Some((pack_no, _, first)) if *pack_no == self.pack_no => {
+                        let message = format!("id `{}` is already used at {}", id, first);
+                        self.error(location, message);
+                    }
+                    Some((_, first_list, _)) if *first_list == list => (),
+                    Some((_, _, first)) if list == "arena" => {
+                        let message = format!("id `{}` is already used at {}", id, first);
+                        self.error(location, message);
+                    }
+                    Some((_, _, first)) => {
+                        let message = format!("id `{}` is used at {}, only actions of the same round and kind can be replaced",
+                            id, first);
+                        self.error(location, message);
+                    }
+                    None => {
+                        self.ids.insert(id.clone(), (self.pack_no, list, at));
+                    }
+                }
+            }
+            Some(v) => self.error(location, format!("`id` must be a string, found {}", v)),
+            None => (),
+        }
+    }
+
+    fn check_action(&mut self, location: &str, action: &Value, fatal: bool) {
+        if !action.is_object() {
+            self.error(location, String::from("action must be an object"));
+            return;
+        }
+        for key in action.as_object().unwrap().keys() {
+            if !["id", "msg", "msgs", "tributes", "killer", "killed", "alliance", "betray",
+                "requires", "consumes", "gives", "applies", "cures", "winner", "stat",
+                "weight", "once_per_game", "cooldown", "when"].contains(&key.as_str()) {
+                self.warning(location, format!("unknown key `{}`", key));
+            }
+        }
+        self.check_id(location, action.get("id"));
+        let tributes = match action.get("tributes") {
+            Some(v) => match v.as_u64() {
+                Some(0) => {
+                    self.error(location, String::from("`tributes` must be at least 1"));
+                    None
+                }
+                Some(n) => Some(n),
+                None => {
+                    self.error(location, format!("`tributes` must be a positive integer, found {}", v));
+                    None
+                }
+            },
+            None => {
+                self.error(location, String::from("missing `tributes`"));
+                None
+            }
+        };
+        self.check_indices(location, action, "killer", tributes);
+        let n_killed = self.check_indices(location, action, "killed", tributes);
+        if fatal && n_killed == 0 {
+            self.warning(location, String::from("fatal action has an empty or missing `killed` list"));
+        }
+        if !fatal && n_killed > 0 {
+            self.warning(location, String::from("nonfatal action kills tributes"));
+        }
+        let n_allied = self.check_indices(location, action, "alliance", tributes);
+        if n_allied == 1 {
+            self.error(location, String::from("`alliance` needs at least 2 tributes"));
+        }
+        match action.get("betray") {
+            Some(Value::Bool(true)) if n_allied > 0 => {
+                self.warning(location, String::from("action both forms an alliance and betrays"));
+            }
+            Some(Value::Bool(_)) | None => (),
+            Some(v) => self.error(location, format!("`betray` must be true or false, found {}", v)),
+        }
+        if let Some(Value::Array(allied)) = action.get("alliance") {
+            for kd in action.get("killed").and_then(|k| k.as_array()).into_iter().flatten() {
+                if allied.contains(kd) {
+                    self.warning(location, format!("tribute {} is killed by the alliance it joins", kd));
+                }
+            }
+        }
+        for field in ["requires", "consumes"].iter() {
+            for tag in self.check_tags(location, action.get(*field), field, "item tag", tributes) {
+                let at = self.locate(location);
+                self.needed.entry(tag).or_insert(at);
+            }
+        }
+        for tag in self.check_tags(location, action.get("gives"), "gives", "item tag", tributes) {
+            self.given.insert(tag);
+        }
+        for field in ["applies", "cures"].iter() {
+            for status in self.check_tags(location, action.get(*field), field, "status", tributes) {
+                if status.parse::<tribute::Status>().is_err() {
+                    self.error(location, format!("`{}` names unknown status `{}` (expected one of {})",
+                        field, status, tribute::STATUS_NAMES.join("/")));
+                }
+            }
+        }
+        if let Some(v) = action.get("winner") {
+            let killed = action.get("killed").and_then(|k| k.as_array());
+            match (v.as_u64(), tributes) {
+                (Some(idx), Some(n)) if idx >= n => {
+                    self.error(location, format!("`winner` index {} is out of range for {} tributes", idx, n));
+                }
+                (Some(idx), _) if killed.is_some_and(|k| k.iter().any(|kd| kd.as_u64() == Some(idx))) => {
+                    self.warning(location, format!("`winner` {} is also killed", idx));
+                }
+                (Some(_), _) => (),
+                (None, _) => self.error(location, format!("`winner` must be a tribute index, found {}", v)),
+            }
+        }
+        self.check_weight(location, action.get("weight"));
+        if let Some(when) = action.get("when") {
+            self.check_when(location, when, tributes);
+        }
+        match action.get("once_per_game") {
+            Some(Value::Bool(true)) if action.get("cooldown").is_some() => {
+                self.warning(location, String::from("`cooldown` has no effect on a `once_per_game` action"));
+            }
+            Some(Value::Bool(_)) | None => (),
+            Some(v) => self.error(location, format!("`once_per_game` must be true or false, found {}", v)),
+        }
+        if let Some(cooldown) = action.get("cooldown") {
+            if cooldown.as_u64().is_none() {
+                self.error(location, format!("`cooldown` must be a number of rounds, found {}", cooldown));
+            }
+        }
+        if let Some(stat) = action.get("stat") {
+            if !stat.as_str().is_some_and(|s| tribute::STAT_NAMES.contains(&s)) {
+                self.error(location, format!("`stat` must be one of {}, found {}", tribute::STAT_NAMES.join("/"), stat));
+            }
+        }
+        match action.get("msg").map(|m| m.as_str()) {
+            Some(Some(msg)) if msg.trim().is_empty() => self.error(location, String::from("`msg` is empty")),
+            Some(Some(msg)) => self.check_template(location, msg, tributes, true),
+            Some(None) => self.error(location, String::from("`msg` must be a string")),
+            None => self.error(location, String::from("missing `msg`")),
+        }
+        for (code, msg) in self.check_translations(location, action.get("msgs"), "msgs") {
+            self.check_template(&format!("{}.msgs.{}", location, code), &msg, tributes, false);
+        }
+    }
+
+    /// Checks a `msgs`/`titles` map is an object of strings, returning them
+    fn check_translations(&mut self, location: &str, v: Option<&Value>, field: &str) -> Vec<(String, String)> {
+        let map = match v {
+            Some(Value::Object(map)) => map,
+            Some(_) => {
+                self.error(location, format!("`{}` must be an object keyed by locale, e.g. {{\"de\": \"...\"}}", field));
+                return Vec::new();
+            }
+            None => return Vec::new(),
+        };
+        let mut found: Vec<(String, String)> = Vec::new();
+        for (code, text) in map.iter() {
+            match text.as_str() {
+                Some(text) if text.trim().is_empty() => self.error(location, format!("`{}.{}` is empty", field, code)),
+                Some(text) => found.push((code.clone(), text.to_string())),
+                None => self.error(location, format!("`{}.{}` must be a string", field, code)),
+            }
+        }
+        found
+    }
+
+    fn check_custom(&mut self, location: &str, round: &Value, names: &[&str]) {
+        let name = match round.get("name").and_then(|n| n.as_str()) {
+            Some(name) if !name.trim().is_empty() => name,
+            _ => {
+                self.error(location, String::from("missing `name` string"));
+                ""
+            }
+        };
+        if events::BUILTIN_ROUNDS.contains(&name) {
+            self.error(location, format!("`{}` is a built-in round", name));
+        }
+        if names.iter().filter(|n| **n == name).count() > 1 {
+            self.error(location, format!("duplicate round name `{}`", name));
+        }
+        match round.get("after").and_then(|a| a.as_str()) {
+            Some(after) if events::BUILTIN_ROUNDS.contains(&after) || names.contains(&after) => {
+                if after == name && round.get("max_per_game").is_none() {
+                    self.error(location, String::from("a round that follows itself needs `max_per_game`"));
+                }
+            }
+            Some(after) => self.error(location, format!("`after` names unknown round `{}`", after)),
+            None => self.error(location, String::from("missing `after` round name")),
+        }
+        if let Some(chance) = round.get("chance") {
+            if !chance.as_f64().is_some_and(|c| (0.0..=1.0).contains(&c)) {
+                self.error(location, format!("`chance` must be between 0 and 1, found {}", chance));
+            }
+        }
+        for key in ["min_day", "max_day", "max_per_game"].iter() {
+            if let Some(v) = round.get(*key) {
+                if v.as_u64().is_none() {
+                    self.error(location, format!("`{}` must be a non-negative integer, found {}", key, v));
+                }
+            }
+        }
+        if let Some(v) = round.get("fatality_bonus") {
+            if v.as_i64().is_none() {
+                self.error(location, format!("`fatality_bonus` must be an integer, found {}", v));
+            }
+        }
+        self.check_round(location, Some(round), false);
+    }
+
+    /// `addition` is for an add-on's section of a fixed round, where every
+    /// key is optional
+    fn check_round(&mut self, location: &str, round: Option<&Value>, addition: bool) {
+        let round = match round {
+            Some(Value::Object(round)) => round,
+            Some(_) => {
+                self.error(location, String::from("round must be an object"));
+                return;
+            }
+            None => {
+                self.error(location, String::from("missing round"));
+                return;
+            }
+        };
+        match round.get("title").map(|t| t.as_str()) {
+            Some(Some(title)) => {
+                let mut tt = TinyTemplate::new();
+                if let Err(e) = tt.add_template("lint", title) {
+                    self.error(&format!("{}.title", location), format!("template does not compile: {}", e));
+                }
+            }
+            None if addition => (),
+            _ => self.error(location, String::from("missing `title` string")),
+        }
+        for (code, title) in self.check_translations(location, round.get("titles"), "titles") {
+            let mut tt = TinyTemplate::new();
+            if let Err(e) = tt.add_template("lint", &title) {
+                self.error(&format!("{}.titles.{}", location, code), format!("template does not compile: {}", e));
+            }
+        }
+        self.check_weight(location, round.get("weight"));
+
+        // message -> first location it was seen at, for duplicate detection
+        let mut seen: HashMap<String, String> = HashMap::new();
+        for (kind, fatal) in [("nonfatal", false), ("fatal", true)].iter() {
+            let actions = match round.get(*kind) {
+                Some(Value::Array(actions)) => actions,
+                Some(_) => {
+                    self.error(location, format!("`{}` must be an array", kind));
+                    continue;
+                }
+                None if addition => continue,
+                None => {
+                    self.error(location, format!("missing `{}` array", kind));
+                    continue;
+                }
+            };
+            if actions.is_empty() && !addition {
+                if *fatal {
+                    self.warning(location, format!("`{}` is empty", kind));
+                }
+                else {
+                    self.error(location, format!("`{}` is empty", kind));
+                }
+            }
+            let mut has_single = false;
+            for (i, action) in actions.iter().enumerate() {
+                let action_location = format!("{}.{}[{}]", location, kind, i);
+                self.check_action(&action_location, action, *fatal);
+                let conditional = ["when", "requires", "consumes"].iter().any(|key| action.get(*key).is_some());
+                if action.get("tributes").and_then(|t| t.as_u64()) == Some(1) && !conditional {
+                    has_single = true;
+                }
+                if let Some(msg) = action.get("msg").and_then(|m| m.as_str()) {
+                    match seen.get(msg) {
+                        Some(first) => self.warning(&action_location, format!("duplicate of {}", first)),
+                        None => {
+                            seen.insert(msg.to_string(), action_location);
+                        }
+                    }
+                }
+            }
+            if !*fatal && !actions.is_empty() && !has_single && !addition {
+                self.warning(location, String::from("no unconditional single-tribute nonfatal action, rounds may stall"));
+            }
+        }
+    }
+}
+
+impl Linter {
+    fn new() -> Linter {
+        Linter{issues:Vec::new(), given:HashSet::new(), needed:BTreeMap::new(), ids:HashMap::new(),
+            custom_names:Vec::new(), pack_no:0, pack:String::new()}
+    }
+
+    /// Checks one pack; add-ons (every pack after the first) may leave out
+    /// any section
+    fn check_pack(&mut self, v: &Value) {
+        let addon = self.pack_no > 0;
+        if !v.is_object() {
+            self.error("", String::from("event pack must be a JSON object"));
+            return;
+        }
+        for key in ["bloodbath", "day", "night", "feast"].iter() {
+            match v.get(*key) {
+                None if addon => (),
+                round => self.check_round(key, round, addon),
+            }
+        }
+        match v.get("arena") {
+            Some(Value::Array(arena)) => {
+                if arena.is_empty() && !addon {
+                    self.warning("arena", String::from("no arena events, ARENA rounds will never happen"));
+                }
+                for (i, round) in arena.iter().enumerate() {
+                    let location = format!("arena[{}]", i);
+                    self.check_id(&location, round.get("id"));
+                    self.check_round(&location, Some(round), false);
+                }
+            }
+            Some(_) => self.error("arena", String::from("`arena` must be an array")),
+            None if addon => (),
+            None => self.warning("arena", String::from("no arena events, ARENA rounds will never happen")),
+        }
+        match v.get("custom") {
+            Some(Value::Array(custom)) => {
+                self.custom_names.extend(custom.iter().filter_map(|c| c.get("name").and_then(|n| n.as_str()))
+                    .map(String::from));
+                let names: Vec<String> = self.custom_names.clone();
+                let names: Vec<&str> = names.iter().map(String::as_str).collect();
+                for (i, round) in custom.iter().enumerate() {
+                    self.check_custom(&format!("custom[{}]", i), round, &names);
+                }
+            }
+            Some(_) => self.error("custom", String::from("`custom` must be an array")),
+            None => (),
+        }
+        match v.get("disable") {
+            Some(_) if !addon => self.warning("disable", String::from("only add-on packs can disable actions")),
+            Some(Value::Array(ids)) => {
+                for id in ids.iter() {
+                    match id.as_str() {
+                        Some(id) if self.ids.get(id).is_some_and(|(pack_no, _, _)| *pack_no < self.pack_no) => (),
+                        Some(id) => self.error("disable", format!("no earlier action has id `{}`", id)),
+                        None => self.error("disable", format!("expected an action id, found {}", id)),
+                    }
+                }
+            }
+            Some(_) => self.error("disable", String::from("`disable` must be an array of action ids")),
+            None => (),
+        }
+    }
+
+    fn finish(mut self) -> Vec<LintIssue> {
+        self.pack.clear();
+        let needed = std::mem::take(&mut self.needed);
+        for (tag, location) in needed.iter() {
+            if !self.given.contains(tag) {
+                self.warning(location, format!("item `{}` is needed but no action gives it", tag));
+            }
+        }
+        self.issues
+    }
+}
+
+/// Checks an event pack in its raw JSON form
+///
+/// Unlike `EventSet::load` this keeps going past malformed entries, and also
+/// reports template problems that would otherwise only show up mid-game.
+pub fn lint_events(v: &Value) -> Vec<LintIssue> {
+    let mut linter = Linter::new();
+    linter.check_pack(v);
+    linter.finish()
+}
+
+/// Reads and lints an event pack file
+pub fn lint_events_file(path: &Path) -> Vec<LintIssue> {
+    lint_events_files(&[path])
+}
+
+/// Reads and lints a base pack followed by its add-ons, as `EventSet::load_packs`
+/// would merge them; with several packs each location starts with its file
+pub fn lint_events_files(paths: &[&Path]) -> Vec<LintIssue> {
+    let mut linter = Linter::new();
+    for (pack_no, path) in paths.iter().enumerate() {
+        linter.pack_no = pack_no;
+        if paths.len() > 1 {
+            linter.pack = events::pack_name(path);
+        }
+        match events::read_pack(path) {
+            Ok(v) => linter.check_pack(&v),
+            Err(e) => linter.error(&path.display().to_string(), e.message),
+        }
+    }
+    linter.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A pack with the same round everywhere, its actions given as JSON
+    fn pack(nonfatal: Value, fatal: Value) -> Value {
+        let round = json!({ "title": "Day {0}", "nonfatal": nonfatal, "fatal": fatal });
+        json!({ "bloodbath": round, "day": round, "night": round, "feast": round, "arena": [] })
+    }
+
+    fn rest() -> Value {
+        json!([{ "msg": "{0.name} rests.", "tributes": 1 }])
+    }
+
+    fn errors(issues: &[LintIssue]) -> Vec<String> {
+        issues.iter().filter(|i| i.severity == Severity::Error).map(|i| i.to_string()).collect()
+    }
+
+    fn warnings(issues: &[LintIssue]) -> Vec<String> {
+        issues.iter().filter(|i| i.severity == Severity::Warning).map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn bundled_pack_is_clean() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("data").join("events.json");
+        let issues = lint_events_file(&path);
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn indices_and_placeholders_are_checked_against_tributes() {
+        let v = pack(rest(), json!([
+            { "msg": "{0.name} kills {2.name}.", "tributes": 2, "killer": [0], "killed": [2] },
+            { "msg": "{0.nmae} falls.", "tributes": 1, "killed": [0] },
+            { "msg": "{name} falls.", "tributes": 1, "killed": [0] },
+        ]));
+        let errors = errors(&lint_events(&v));
+        assert!(errors.contains(&String::from("day.fatal[0]: error: `killed` index 2 is out of range for 2 tributes")));
+        assert!(errors.contains(&String::from(
+            "day.fatal[0]: error: placeholder `{2.name}` refers to tribute 2 but `tributes` is 2")));
+        assert!(errors.iter().any(|e| e.starts_with("day.fatal[1]: error: placeholder `{0.nmae}` uses unknown field")));
+        assert!(errors.contains(&String::from("day.fatal[2]: error: placeholder `{name}` does not name a tribute index")));
+    }
+
+    #[test]
+    fn missing_fields_and_bad_templates_are_errors() {
+        let v = pack(rest(), json!([
+            { "tributes": 1, "killed": [0] },
+            { "msg": "{0.name falls.", "tributes": 1, "killed": [0] },
+            { "msg": "{0.name} falls." },
+        ]));
+        let errors = errors(&lint_events(&v));
+        assert!(errors.contains(&String::from("day.fatal[0]: error: missing `msg`")));
+        assert!(errors.iter().any(|e| e.starts_with("day.fatal[1]: error: template does not compile")));
+        assert!(errors.contains(&String::from("day.fatal[2]: error: missing `tributes`")));
+    }
+
+    #[test]
+    fn suspicious_actions_are_warnings() {
+        let v = pack(
+            json!([
+                { "msg": "{0.name} rests.", "tributes": 2 },
+                { "msg": "{0.name} aims.", "tributes": 1, "requires": { "0": ["bow"] } },
+            ]),
+            json!([{ "msg": "{0.name} trips. he is fine.", "tributes": 1 }]));
+        let issues = lint_events(&v);
+        assert!(errors(&issues).is_empty(), "{:?}", issues);
+        let warnings = warnings(&issues);
+        for expected in ["day.nonfatal[0]: warning: tribute 1 takes part but is never mentioned",
+                "day: warning: no unconditional single-tribute nonfatal action, rounds may stall",
+                "bloodbath.nonfatal[1]: warning: item `bow` is needed but no action gives it",
+                "day.fatal[0]: warning: fatal action has an empty or missing `killed` list"].iter() {
+            assert!(warnings.contains(&expected.to_string()), "missing {:?} in {:?}", expected, warnings);
+        }
+    }
+
+    #[test]
+    fn pronouns_are_checked_for_sentence_starts_and_verb_agreement() {
+        let v = pack(rest(), json!([{ "msg": "{0.name} falls. {0.genN} is gone.", "tributes": 1, "killed": [0] }]));
+        let warnings = warnings(&lint_events(&v));
+        assert!(warnings.contains(&String::from("day.fatal[0]: warning: `{0.genN}` starts a sentence, use `{0.GenN}`")));
+        assert!(warnings.contains(&String::from(
+            "day.fatal[0]: warning: `{0.genN} is` reads wrong for plural pronouns, use `{0.is}`")));
+    }
+
+    #[test]
+    fn translations_may_not_use_verb_fields() {
+        let v = pack(rest(), json!([{ "msg": "{0.name} {0.is} gone.", "tributes": 1, "killed": [0],
+            "msgs": { "de": "{0.name} {0.is} weg." } }]));
+        let errors = errors(&lint_events(&v));
+        assert!(errors.contains(&String::from(
+            "day.fatal[0].msgs.de: error: placeholder `{0.is}` only renders English verb forms, write the verb out")));
+        assert!(errors.iter().all(|e| e.contains(".fatal[0].msgs.de: ")));
+    }
+
+    #[test]
+    fn addon_ids_may_replace_earlier_packs_but_not_repeat() {
+        let action = |id: &str, msg: &str| json!({ "id": id, "msg": msg, "tributes": 1 });
+        let mut base = pack(rest(), json!([]));
+        base["day"]["nonfatal"] = json!([action("rest", "{0.name} rests.")]);
+        let mut linter = Linter::new();
+        linter.check_pack(&base);
+        linter.pack_no = 1;
+        linter.check_pack(&json!({
+            "disable": ["rest", "nothing"],
+            "day": { "nonfatal": [action("hunt", "{0.name} hunts."), action("hunt", "{0.name} hunts again.")] },
+        }));
+        let errors = errors(&linter.finish());
+        assert!(errors.contains(&String::from("day.nonfatal[1]: error: id `hunt` is already used at day.nonfatal[0]")));
+        assert!(errors.contains(&String::from("disable: error: no earlier action has id `nothing`")));
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+    }
+
+    #[test]
+    fn addon_ids_follow_the_loader_rules() {
+        let action = |id: &str, msg: &str| json!({ "id": id, "msg": msg, "tributes": 1 });
+        let mut base = pack(rest(), json!([]));
+        base["day"]["nonfatal"] = json!([action("rest", "{0.name} rests.")]);
+        base["arena"] = json!([{ "id": "fire", "title": "Fire", "nonfatal": [action("run", "{0.name} runs.")],
+            "fatal": [] }]);
+        let addon = json!({
+            "night": { "nonfatal": [action("fire", "{0.name} lights a fire."), action("rest", "{0.name} naps.")] },
+            "day": { "fatal": [action("run", "{0.name} runs.")] },
+            "arena": [
+                { "id": "fire", "title": "Wildfire", "nonfatal": [action("run", "{0.name} flees.")], "fatal": [] },
+                { "id": "rest", "title": "Calm", "nonfatal": [action("calm", "{0.name} naps.")], "fatal": [] },
+            ],
+        });
+        let mut linter = Linter::new();
+        linter.check_pack(&base);
+        linter.pack_no = 1;
+        linter.check_pack(&addon);
+        let errors = errors(&linter.finish());
+        assert_eq!(errors, vec![
+            "day.fatal[0]: error: id `run` is used at arena[0].nonfatal[0], only actions of the same round and kind can be \
+                replaced",
+            "night.nonfatal[0]: error: id `fire` is used at arena[0], only actions of the same round and kind can be \
+                replaced",
+            "night.nonfatal[1]: error: id `rest` is used at day.nonfatal[0], only actions of the same round and kind can be \
+                replaced",
+            "arena[1]: error: id `rest` is already used at day.nonfatal[0]",
+        ]);
+        let mut events = events::EventSet::from_value(&base).unwrap();
+        assert!(events.merge(&addon, "addon.json").is_err());
+    }
+
+    #[test]
+    fn unknown_condition_is_an_error() {
+        let v = pack(rest(), json!([{ "msg": "{0.name} kills {1.name}.", "tributes": 2, "killer": [0], "killed": [1],
+            "when": { "min_dya": 2 } }]));
+        let issues = lint_events(&v);
+        assert!(errors(&issues).iter().any(|e| e.contains("unknown condition `when.min_dya`")));
+        assert!(events::EventSet::from_value(&v).is_err());
+    }
+}
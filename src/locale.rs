@@ -0,0 +1,310 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use serde::Deserialize;
+use serde_json::Value;
+use tinytemplate::{TinyTemplate, format_unescaped};
+
+use super::tribute::{Gender, Pronouns, Status};
+
+/// The locale used when none is asked for; its strings are built in
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Console and summary text, English unless a locale file says otherwise
+///
+/// `{0}` in `cannon_shots` is the number of shots and in `budget_left` the
+/// budget; `sponsor_help` takes `{budget}` and `{gifts}`. The sponsor errors
+/// take `{name}`, `{gift}`, `{cost}` and `{budget}` as they apply, and the
+/// messages about files `{path}` and `{error}`, plus `{format}` for `convert`.
+/// `simulating` takes the number of runs as `{0}`, `lint_summary` `{files}`,
+/// `{errors}` and `{warnings}`, and `pack_actions` `{pack}` and the count `{0}`.
+///
+/// Lint and load problems themselves are reported in English.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Strings {
+    pub seed: String,
+    /// `render`'s closing line, with `{0}` the seed and `{path}` the output directory
+    pub images_written: String,
+    pub cannon_shots: String,
+    pub died_today: String,
+    pub game_complete: String,
+    pub name: String,
+    pub district: String,
+    pub kills: String,
+    pub died: String,
+    pub survivor: String,
+    pub survivors: String,
+    pub sponsor_title: String,
+    /// default parachute message, `{gift}` being the gift's name
+    pub parachute: String,
+    pub sponsor_help: String,
+    pub sponsor_usage: String,
+    pub budget_left: String,
+    pub no_gift: String,
+    pub no_tribute: String,
+    pub tribute_dead: String,
+    pub gift_too_costly: String,
+    pub settings_missing: String,
+    pub invalid_settings: String,
+    pub invalid_roster: String,
+    pub invalid_events: String,
+    pub lint_summary: String,
+    pub simulating: String,
+    pub pack_actions: String,
+    pub cannot_read: String,
+    pub cannot_convert: String,
+    pub lossy_conversion: String,
+    pub wrote: String,
+    pub cannot_write: String,
+    /// a game log line that could not be written, `{0}` being the error
+    pub log_error: String,
+    pub cannot_write_log: String,
+    pub builder: BuilderStrings,
+    pub batch: BatchStrings,
+    /// off-screen death messages, replacing `Status::death_msg`
+    pub status_deaths: BTreeMap<Status, String>,
+}
+
+/// Text of the `hunger simulate` report
+///
+/// `batch_complete` takes `{runs}`, `{first}` and `{last}` seed,
+/// `average_length` the days as `{0}` and `capped` `{runs}` and `{share}`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatchStrings {
+    pub batch_complete: String,
+    pub average_length: String,
+    pub capped: String,
+    pub win_rate: String,
+    pub placement: String,
+    pub round_type: String,
+    pub deaths: String,
+    pub share: String,
+}
+
+impl Default for BatchStrings {
+    fn default() -> BatchStrings {
+        let s = String::from;
+        BatchStrings{
+            batch_complete: s("Batch Complete: {runs} runs (seeds {first}..{last})"),
+            average_length: s("Average game length: {0} days"),
+            capped: s("Stopped at max_days without a winner: {runs} runs ({share}%)"),
+            win_rate: s("Win %"),
+            placement: s("Place"),
+            round_type: s("Round type"),
+            deaths: s("Deaths"),
+            share: s("Share"),
+        }
+    }
+}
+
+/// Text of `hunger interactive`, the roster builder
+///
+/// `{0}` is the tribute number, count or entry the message is about, and the
+/// save messages take `{path}` and `{error}`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuilderStrings {
+    pub welcome: String,
+    pub help: String,
+    pub prompt: String,
+    pub usage_add: String,
+    pub usage_save: String,
+    pub added: String,
+    pub removed: String,
+    pub saved: String,
+    pub cannot_save: String,
+    pub too_few: String,
+    pub duplicate_name: String,
+    pub unknown_command: String,
+    pub bad_index: String,
+    pub bad_gender: String,
+    pub bad_pronouns: String,
+    pub bad_district: String,
+    pub ask_name: String,
+    pub ask_gender: String,
+    pub ask_pronouns: String,
+    pub ask_avatar: String,
+    pub ask_district: String,
+}
+
+impl Default for BuilderStrings {
+    fn default() -> BuilderStrings {
+        let s = String::from;
+        BuilderStrings{
+            welcome: s("-----------------------------------------\n\
+                Welcome to the Hunger Game Simulator v0.1\n\
+                Author: Akharis Ren\n\
+                -----------------------------------------"),
+            help: s("Commands:\n\
+                \x20 help                         show this message\n\
+                \x20 list                         show the current roster\n\
+                \x20 add <name> <gender> [avatar] add a tribute, gender is M, F or A\n\
+                \x20 remove <n>                   remove tribute n\n\
+                \x20 edit <n>                     change the name, gender, pronouns, avatar or district of tribute n\n\
+                \x20 shuffle                      shuffle the roster order\n\
+                \x20 save <file>                  write the roster to a file\n\
+                \x20 load [file]                  replace the roster with one read from a file\n\
+                \x20 start                        play a game with this roster\n\
+                \x20 quit                         leave without playing"),
+            prompt: s("[{0} tributes] >"),
+            usage_add: s("usage: add <name> <gender> [avatar]"),
+            usage_save: s("usage: save <file>"),
+            added: s("added ({0}): {1}"),
+            removed: s("removed {0}"),
+            saved: s("saved {0} tributes to {path}"),
+            cannot_save: s("cannot save {path}: {error}"),
+            too_few: s("a game needs at least 2 tributes"),
+            duplicate_name: s("there is already a tribute called `{0}`"),
+            unknown_command: s("unknown command `{0}`, enter `help` for a list"),
+            bad_index: s("expected a tribute number between 0 and {0}"),
+            bad_gender: s("gender must be M, F or A"),
+            bad_pronouns: s("pronouns are nominative/accusative/genitive/reflexive, with /plural for plural verbs"),
+            bad_district: s("districts are numbered from 1"),
+            ask_name: s("Name"),
+            ask_gender: s("Gender (M/F/A)"),
+            ask_pronouns: s("Pronouns, e.g. xe/xem/xyr/xemself ('-' for the gender's)"),
+            ask_avatar: s("Avatar ('-' for none)"),
+            ask_district: s("District ('-' for none)"),
+        }
+    }
+}
+
+impl Default for Strings {
+    fn default() -> Strings {
+        let s = String::from;
+        Strings{
+            seed: s("Seed: {0}"),
+            images_written: s("Seed: {0}, images written to {path}"),
+            cannon_shots: s("{0} cannon shots can be heard from the distance."),
+            died_today: s("The following tributes have died today: "),
+            game_complete: s("Simulation Complete: "),
+            name: s("Name"),
+            district: s("District"),
+            kills: s("Kills"),
+            died: s("Died"),
+            survivor: s("Survivor"),
+            survivors: s("Survivors"),
+            sponsor_title: s("A gift from the sponsors"),
+            parachute: s("A silver parachute drifts down to {0.name}, carrying {gift}."),
+            sponsor_help: s("Press enter for each round. Sponsors can send gifts with `sponsor <tribute> <gift>`,\n\
+                budget {budget}: {gifts}"),
+            sponsor_usage: s("usage: sponsor <tribute> <gift>, or enter to continue"),
+            budget_left: s("({0} left in the sponsor budget)"),
+            no_gift: s("no gift called `{gift}`"),
+            no_tribute: s("no tribute called `{name}`"),
+            tribute_dead: s("{name} is dead"),
+            gift_too_costly: s("{gift} costs {cost}, only {budget} left"),
+            settings_missing: s("{path} doesn't exist! using default settings"),
+            invalid_settings: s("invalid game settings: {error}"),
+            invalid_roster: s("{path} is not a valid roster:"),
+            invalid_events: s("{path} is not a valid event pack:"),
+            lint_summary: s("{files}: {errors} error(s), {warnings} warning(s)"),
+            simulating: s("Simulating {0} games..."),
+            pack_actions: s("{pack}: {0} actions"),
+            cannot_read: s("cannot read {path}: {error}"),
+            cannot_convert: s("cannot write {path} as {format}: {error}"),
+            lossy_conversion: s("{path} does not survive conversion to {format}"),
+            wrote: s("wrote {path}"),
+            cannot_write: s("cannot write {path}: {error}"),
+            log_error: s("log error: {0}"),
+            cannot_write_log: s("cannot write game log {path}: {error}"),
+            builder: BuilderStrings::default(),
+            batch: BatchStrings::default(),
+            status_deaths: BTreeMap::new(),
+        }
+    }
+}
+
+impl Strings {
+    /// Template reported in the FALLEN round for an off-screen death
+    pub fn status_death(&self, status: Status) -> &str {
+        self.status_deaths.get(&status).map(String::as_str).unwrap_or_else(|| status.death_msg())
+    }
+}
+
+/// Fills in a `Strings` template, values going in as they are; a malformed
+/// template comes back unfilled
+pub fn fill(template: &str, context: &Value) -> String {
+    let mut tt = TinyTemplate::new();
+    tt.set_default_formatter(&format_unescaped);
+    tt.add_template("text", template)
+        .and_then(|_| tt.render("text", context))
+        .unwrap_or_else(|_| template.to_string())
+}
+
+/// A `data/locales/<code>.json` file: pronoun tables and game text for one
+/// language
+///
+/// Event packs carry the translated messages and titles themselves, in the
+/// `msgs` and `titles` maps keyed by locale code.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Locale {
+    #[serde(skip)]
+    pub code: String,
+    /// default pronoun sets keyed by gender, `M`, `F` or `A`
+    pub pronouns: BTreeMap<String, Pronouns>,
+    pub strings: Strings,
+}
+
+impl Default for Locale {
+    fn default() -> Locale {
+        Locale{code:String::from(DEFAULT_LOCALE), pronouns:BTreeMap::new(), strings:Strings::default()}
+    }
+}
+
+impl Locale {
+    /// Reads `<dir>/<code>.json`; the default locale needs no file
+    pub fn load(dir: &Path, code: &str) -> Result<Locale, String> {
+        let path = dir.join(format!("{}.json", code));
+        if code == DEFAULT_LOCALE && !path.exists() {
+            return Ok(Locale::default());
+        }
+        let data = fs::read_to_string(&path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut locale: Locale = serde_json::from_str(&data)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        if let Some(gender) = locale.pronouns.keys().find(|g| g.parse::<Gender>().is_err()) {
+            return Err(format!("{}: unknown gender `{}` in pronouns (expected M, F or A)", path.display(), gender));
+        }
+        locale.code = code.to_string();
+        Ok(locale)
+    }
+    /// The pronoun set for a tribute of this gender without one of their own
+    pub fn pronouns_for(&self, gender: &Gender) -> Pronouns {
+        self.pronouns.get(gender.as_str()).cloned().unwrap_or_else(|| Pronouns::for_gender(gender))
+    }
+    /// This locale's entry in a `msgs`/`titles` map, if there is one
+    pub fn translation<'a>(&self, translations: &'a BTreeMap<String, String>) -> Option<&'a str> {
+        translations.get(&self.code).map(String::as_str)
+    }
+    /// Picks this locale's entry from a `msgs`/`titles` map, or `fallback`
+    pub fn pick<'a>(&self, translations: &'a BTreeMap<String, String>, fallback: &'a str) -> &'a str {
+        self.translation(translations).unwrap_or(fallback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn bundled_locales_load() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("data").join("locales");
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let code = path.file_stem().unwrap().to_str().unwrap();
+            let locale = Locale::load(&dir, code).unwrap();
+            assert_eq!(locale.code, code);
+        }
+    }
+
+    #[test]
+    fn fill_inserts_values_as_they_are() {
+        assert_eq!(fill("{name} & {gift}", &json!({ "name": "<Anne>", "gift": "\"bow\"" })), "<Anne> & \"bow\"");
+        assert_eq!(fill("{unclosed", &json!({})), "{unclosed");
+    }
+}
@@ -0,0 +1,119 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use serde::Serialize;
+
+use super::roster;
+use super::game::{RoundResult, RoundType};
+
+/// A tribute as listed in the `game` record
+#[derive(Serialize)]
+pub struct LogTribute {
+    pub id: usize,
+    pub name: String,
+}
+
+/// A tribute's final standing in the `summary` record
+#[derive(Serialize)]
+pub struct LogStanding {
+    pub id: usize,
+    pub kills: i32,
+    /// day of death, `None` for the survivor
+    pub died: Option<i32>,
+}
+
+/// One line of the game log; tributes are always referred to by id
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum LogRecord {
+    Game {
+        seed: u64,
+        tributes: Vec<LogTribute>,
+    },
+    Round {
+        day: i32,
+        round: String,
+        /// the arena event's id, when it has one
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event: Option<String>,
+        title: String,
+        /// tributes who died today, only present for FALLEN rounds
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fallen: Option<Vec<usize>>,
+    },
+    Action {
+        day: i32,
+        round: String,
+        /// the action's id in the event pack, when it has one
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        message: String,
+        participants: Vec<usize>,
+        killers: Vec<usize>,
+        killed: Vec<usize>,
+    },
+    Summary {
+        days: i32,
+        winner: Option<usize>,
+        standings: Vec<LogStanding>,
+    },
+}
+
+/// Writes a game as JSON Lines, one record per round and per action
+pub struct GameLog {
+    out: BufWriter<File>,
+}
+
+impl GameLog {
+    pub fn create(path: &Path) -> io::Result<GameLog> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(GameLog{out:BufWriter::new(File::create(path)?)})
+    }
+
+    pub fn write(&mut self, record: &LogRecord) -> io::Result<()> {
+        serde_json::to_writer(&mut self.out, record)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()
+    }
+
+    pub fn write_start(&mut self, game_roster: &roster::Roster, seed: u64) -> io::Result<()> {
+        let tributes = (0..game_roster.len())
+            .map(|i| {
+                let tb = game_roster.get_tribute(i);
+                LogTribute{id:tb.id, name:tb.name.clone()}
+            })
+            .collect();
+        self.write(&LogRecord::Game{seed, tributes})
+    }
+
+    pub fn write_round(&mut self, game_roster: &roster::Roster, round: &RoundResult) -> io::Result<()> {
+        let ids = |members: &[usize]| -> Vec<usize> {
+            members.iter().map(|&m| game_roster.get_tribute(m).id).collect()
+        };
+        let round_key = &round.name;
+        let fallen = if round.round_type == RoundType::FALLEN { Some(ids(&round.members)) } else { None };
+        self.write(&LogRecord::Round{day:round.day, round:round_key.clone(), event:round.event_id.clone(), title:round.title.clone(), fallen})?;
+        for action in round.actions.iter() {
+            self.write(&LogRecord::Action{day:round.day, round:round_key.clone(), id:action.id.clone(), message:action.message.clone(),
+                participants:ids(&action.members), killers:ids(&action.killers), killed:ids(&action.killed)})?;
+        }
+        Ok(())
+    }
+
+    pub fn write_summary(&mut self, game_roster: &roster::Roster, days: i32) -> io::Result<()> {
+        let standings: Vec<LogStanding> = (0..game_roster.len())
+            .map(|i| {
+                let tb = game_roster.get_tribute(i);
+                LogStanding{id:tb.id, kills:tb.killcount, died:if tb.alive { None } else { Some(tb.deathday) }}
+            })
+            .collect();
+        let winner = if game_roster.n_alive() == 1 {
+            (0..game_roster.len()).map(|i| game_roster.get_tribute(i)).find(|tb| tb.alive).map(|tb| tb.id)
+        } else {
+            None
+        };
+        self.write(&LogRecord::Summary{days, winner, standings})
+    }
+}
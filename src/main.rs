@@ -1,34 +1,28 @@
-/// Main: Hunger Game Simulator v0.1
-/// Author: Akharis Ren
-/// 
-/// This is the main file
-/// 
+//! Main: Hunger Game Simulator v0.1
+//! Author: Akharis Ren
+//!
+//! This is the main file
 
 extern crate rand;
+#[macro_use]
+extern crate clap;
 
-mod tribute;
-mod roster;
-mod game;
-mod img;
+use hunger::{tribute, roster, game, img};
+use hunger::events::{self, EventSet};
+use hunger::lint;
+use hunger::output;
+use hunger::stats;
+use hunger::settings::GameSettings;
+use hunger::locale::{self, fill, BuilderStrings, Locale, Strings};
 
-use std::io;
 use std::fs;
+use std::io;
 use std::path::PathBuf;
 use std::path::Path;
-use std::env;
-use serde_json::{Value};
+use std::process;
 
-use rand::Rng;
-
-fn welcome_text() {
-    println!("-----------------------------------------");
-    println!("Welcome to the Hunger Game Simulator v0.1");
-    println!("Author: Akharis Ren"); 
-    println!("-----------------------------------------");
-    println!("Select an option (1/2)");
-    println!("1. Create roster");
-    println!("2. Exit");
-}
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde_json::json;
 
 /// Reads input from stdin and returns a String
 /// 
@@ -43,308 +37,455 @@ fn read_input() -> String {
     input
 }
 
-fn read_file(filename: String) -> String {
-    let contents = fs::read_to_string(filename)
-        .expect("Something went wrong reading the file");
-
-    contents
+/// Asks for a new value, keeping `current` when the answer is empty
+fn prompt_keep(label: &str, current: &str) -> String {
+    println!("{} [{}]:", label, current);
+    let answer = read_input();
+    let answer = answer.trim();
+    if answer.is_empty() { current.to_string() } else { answer.to_string() }
 }
 
-fn parse_choice() -> i32 {
-    let choice = read_input()
-            .trim()
-            .parse::<i32>();
-    let number = match choice {
-        Ok(number) => number,
-        Err(_) => 0,
-    };
-    number
-}
-
-fn parse_positive_integer(upper_limit: i32) -> i32 {
-    let mut limit = upper_limit;
-    if upper_limit < 0 {
-        // invalid! assume that we have an unlimited input mode
-        limit = i32::max_value();
-    }
-
-    let choice = read_input()
-            .trim()
-            .parse::<i32>();
-    let mut number = match choice {
-        Ok(number) => number,
-        Err(_) => 0,
-    };
-
-    if number < 0 || number > limit {
-        // error value assumed to be -1
-        number = -1;
+/// Parses the tribute number argument of `remove`/`edit`
+fn parse_index(arg: Option<&str>, game_roster: &roster::Roster, text: &BuilderStrings) -> Option<usize> {
+    match arg.map(str::parse::<usize>) {
+        Some(Ok(n)) if n < game_roster.len() => Some(n),
+        _ => {
+            println!("{}", fill(&text.bad_index, &json!({ "0": game_roster.len() as i64 - 1 })));
+            None
+        }
     }
-    number
 }
 
-/// Interactive Roster Creation
-fn interactive_roster_creation(game_roster: &mut roster::Roster) -> i32 {
-    let mut status: i32 = 0;
-
-    println!("----------------------------------");
-    println!("Interative roster creation module.");
-    println!("----------------------------------");
-    println!("Select an option (1/2/3)");
-    println!("1. Create from scratch");
-    println!("2. Create from files");
-    println!("3. Go back");
-
-    let number = parse_choice();
-
-    if number == 1 {
-        status = create_roster_from_scratch(game_roster);
-    }
-    else if number == 2 {
-        status = create_roster_from_files(game_roster);
-    }
-    else if number == 3 {
-        status = 1 
+fn edit_tribute(game_roster: &mut roster::Roster, n: usize, text: &BuilderStrings) {
+    loop {
+        let name = prompt_keep(&text.ask_name, &game_roster.get_tribute(n).name);
+        if game_roster.find(&name).is_some_and(|i| i != n) {
+            println!("{}", fill(&text.duplicate_name, &json!({ "0": name })));
+            continue;
+        }
+        game_roster.get_tribute_mut(n).name = name;
+        break;
     }
-
-    status
-}
-
-fn create_roster_from_scratch(game_roster: &mut roster::Roster) -> i32 {
-    let mut status: i32 = 0;
-    let mut stage: i32 = 0;
-
-    println!("---------------------------");
-    println!("Create roster from scratch.");
-    println!("---------------------------");
-
+    let tb = game_roster.get_tribute_mut(n);
     loop {
-        
-        if stage == 0 {
-            // stage = 0 -> select tribute number
-            println!("How many tributes? (enter a valid integer)");
-
-            // limit is 24, eventually set this value in a settings container
-            let mut number = parse_positive_integer(24);
-            if number == -1 {
-                // error!
-                println!("Invalid input, valid numerical range (0-{})", 24);
-                continue;
-            }
-            else if number == 0 {
-                // technically invalid but assume we want to randomize
-                println!("Input: 0, assume RANDOM (0-{})", 24);
-                let mut rng = rand::thread_rng();
-                number = rng.gen_range(1, 25);
-                println!("Added {} tributes", number);
-            }
-            else {
-                println!("Added!");
-            }
-            // now add tributes
-            for _ in 0..number {
-                let test_tribute = tribute::Tribute::new();
-                game_roster.add_tribute(Box::new(test_tribute));
+        let gender = prompt_keep(&text.ask_gender, tb.gender.as_str());
+        match gender.parse::<tribute::Gender>() {
+            Ok(g) => {
+                tb.gender = g;
+                break;
             }
-
-            // proceed to the next stage
-            stage = 1
+            Err(_) => println!("{}", text.bad_gender),
         }
-        else if stage == 1 {
-            // review creation and make changes if necessary
-            println!("To review roster, enter `review`, for other commands, enter `help`.");
-            let choice: String = read_input();
-            // check choices, if invalid selection, continue
-            if choice.trim() == String::from("review") {
-                println!("Review");
-                println!("{}", game_roster.to_string());
-            }
-            else {
-                println!("Invalid input");
-                continue;
+    }
+    loop {
+        let current = tb.pronouns.as_ref().map(|p| p.as_short()).unwrap_or_else(|| "-".to_string());
+        let pronouns = prompt_keep(&text.ask_pronouns, &current);
+        if pronouns == "-" {
+            tb.pronouns = None;
+            break;
+        }
+        match pronouns.parse::<tribute::Pronouns>() {
+            Ok(p) => {
+                tb.pronouns = Some(p);
+                break;
             }
+            Err(_) => println!("{}", text.bad_pronouns),
         }
-        else {
-            break
+    }
+    let avatar = prompt_keep(&text.ask_avatar, tb.avatar.as_deref().unwrap_or("-"));
+    tb.avatar = if avatar == "-" { None } else { Some(avatar) };
+    loop {
+        let current = tb.district.map(|d| d.to_string()).unwrap_or_else(|| "-".to_string());
+        let district = prompt_keep(&text.ask_district, &current);
+        if district == "-" {
+            tb.district = None;
+            break;
+        }
+        match district.parse::<u32>() {
+            Ok(d) if d >= 1 => {
+                tb.district = Some(d);
+                break;
+            }
+            _ => println!("{}", text.bad_district),
         }
-
-
     }
-
-    status
 }
 
-fn find_data_directory() -> PathBuf {
-    env::current_dir()
-        .expect("Cannot access current directory")
-        .as_path().join(Path::new("data"))
-}
-
-fn create_roster_from_files(game_roster: &mut roster::Roster) -> i32 {
-    let mut status: i32 = 0;
-    let datadir = find_data_directory();
-    let mut stage: i32 = 0;
+/// `hunger interactive`: build a roster with typed commands, then play it
+fn run_interactive(paths: &Paths, m: &ArgMatches, game_locale: &Locale) -> i32 {
+    let strings = &game_locale.strings;
+    let text = &strings.builder;
+    println!("{}", text.welcome);
+    println!("{}", text.help);
 
+    let mut game_roster: roster::Roster = roster::Roster::new();
     loop {
-        if stage == 0 {
-            println!("Enter file to read (default: roster.json)");
-            let choice = read_input();
-            let mut slice_choice = choice.trim();
-            if slice_choice.ends_with(".json") {
-                slice_choice = &slice_choice[..slice_choice.len()-5];
-            }
-            
-            let roster_path = Path::new(slice_choice);
-            let mut abs_pathbuf = datadir.join(roster_path);
-            abs_pathbuf.set_extension("json");
-
-            if ! abs_pathbuf.exists() {
-                println!("{} doesn't exist! defaulting...", abs_pathbuf.display());
-                abs_pathbuf = datadir.join("roster.json");
+        println!("{}", fill(&text.prompt, &json!({ "0": game_roster.len() })));
+        let line = read_input();
+        if line.is_empty() {
+            // stdin closed
+            return 0;
+        }
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(command) => command,
+            None => continue,
+        };
+        let args: Vec<&str> = words.collect();
+
+        match command {
+            "help" => println!("{}", text.help),
+            "list" | "review" => print!("{}", game_roster),
+            "add" => {
+                if args.len() < 2 || args.len() > 3 {
+                    println!("{}", text.usage_add);
+                    continue;
+                }
+                if args[1].parse::<tribute::Gender>().is_err() {
+                    println!("{}", text.bad_gender);
+                    continue;
+                }
+                if game_roster.find(args[0]).is_some() {
+                    println!("{}", fill(&text.duplicate_name, &json!({ "0": args[0] })));
+                    continue;
+                }
+                let mut tb = tribute::Tribute::from_data(args[0], args[1], "");
+                tb.avatar = args.get(2).map(|a| a.to_string());
+                println!("{}", fill(&text.added, &json!({ "0": game_roster.len(), "1": tb.to_string() })));
+                game_roster.add_tribute(tb);
             }
-
-            if ! abs_pathbuf.exists() {
-                println!("default file doesn't exist! PANIC");
+            "remove" => {
+                if let Some(n) = parse_index(args.first().copied(), &game_roster, text) {
+                    println!("{}", fill(&text.removed, &json!({ "0": game_roster.remove_tribute(n).to_string() })));
+                }
             }
-
-            println!("{} found!", abs_pathbuf.display());
-
-            // now read as json
-            let data = fs::read_to_string(abs_pathbuf)
-                .expect("Something went wrong reading the file");
-
-            let mut v: Value = Value::Null;
-            match serde_json::from_str(data.as_str()) {
-                Ok(result) => v = result,
-                Err(e) => println!("Error {}", e)
+            "edit" => {
+                if let Some(n) = parse_index(args.first().copied(), &game_roster, text) {
+                    edit_tribute(&mut game_roster, n, text);
+                    println!("({}): {}", n, game_roster.get_tribute(n));
+                }
             }
-
-            // now read v for number of tributes
-            let number_of_tributes = v["number_of_tributes"].as_i64().unwrap();
-
-            println!("{}", number_of_tributes);
-
-            // the tributes should now be accessed correctly 
-            for i in 1..number_of_tributes+1 {
-                let test_tribute = tribute::Tribute::from_data(
-                    v[format!("{}", i)]["name"].as_str().unwrap(),
-                    v[format!("{}", i)]["gender"].as_str().unwrap(),
-                    v[format!("{}", i)]["avatar"].as_str().unwrap());
-                game_roster.add_tribute(Box::new(test_tribute));
+            "shuffle" => {
+                game_roster.shuffle(&mut rand::thread_rng());
+                print!("{}", game_roster);
             }
-            stage = 1;
-        }
-        else if stage == 1 {
-            // review creation and make changes if necessary
-            println!("To review roster, enter `review`, for other commands, enter `help`.");
-            let choice: String = read_input();
-            // check choices, if invalid selection, continue
-            if choice.trim() == String::from("review") {
-                println!("Review");
-                println!("{}", game_roster.to_string());
+            "save" => {
+                let path = match args.first() {
+                    Some(file) => PathBuf::from(file),
+                    None => {
+                        println!("{}", text.usage_save);
+                        continue;
+                    }
+                };
+                let context = |error: String| json!({ "0": game_roster.len(), "path": path.display().to_string(), "error": error });
+                match game_roster.save(&path) {
+                    Ok(_) => println!("{}", fill(&text.saved, &context(String::new()))),
+                    Err(e) => println!("{}", fill(&text.cannot_save, &context(e.to_string()))),
+                }
             }
-            else if choice.trim() == String::from("continue") {
-                status = 0;
-                break;
+            "load" => {
+                let path = args.first().map(PathBuf::from).unwrap_or_else(|| paths.roster.clone());
+                if let Some(loaded) = load_roster(&path, strings) {
+                    game_roster = loaded;
+                    print!("{}", game_roster);
+                }
             }
-            else {
-                println!("Invalid input");
-                continue;
+            "start" => {
+                if game_roster.len() < 2 {
+                    println!("{}", text.too_few);
+                    continue;
+                }
+                return play(paths, m, game_roster, false, game_locale);
             }
+            "quit" | "exit" => return 0,
+            _ => println!("{}", fill(&text.unknown_command, &json!({ "0": command }))),
         }
     }
-
-    status
 }
 
-fn run_interactive() {
-    let mut number = 0;
-    let mut game_roster: roster::Roster = roster::Roster::new();
-    loop {
-        if number == 0 {
-            welcome_text();
-        }
-        else if number == 1 {
-            let status = interactive_roster_creation(&mut game_roster);
-            if status == 1 {
-                number = 0;
-            }
-            else if status == 0 {
-                number = 384;
+/// Loads a roster file, printing every problem found if it is malformed
+fn load_roster(path: &Path, strings: &Strings) -> Option<roster::Roster> {
+    match roster::Roster::load(path) {
+        Ok(loaded) => Some(loaded),
+        Err(errors) => {
+            println!("{}", fill(&strings.invalid_roster, &json!({ "path": path.display().to_string() })));
+            for e in errors.iter() {
+                println!("  {}", e);
             }
+            None
         }
-        else if number == 2 {
-            break;
-        }
-        else {
-            println!("invalid selection\n");
-        }
-        if number == 384 {
-            // roster complete, run simulation!
-            // TODO maybe add a way to check the game settings
-            let status = game::gameloop(&mut game_roster);
-        }
+    }
+}
 
-        number = parse_choice();
+/// Loads the event packs in order, printing every problem found in the
+/// first malformed one
+fn load_events(paths: &[PathBuf], strings: &Strings) -> Option<EventSet> {
+    let paths: Vec<&Path> = paths.iter().map(PathBuf::as_path).collect();
+    match EventSet::load_packs(&paths) {
+        Ok(events) => Some(events),
+        Err((path, errors)) => {
+            println!("{}", fill(&strings.invalid_events, &json!({ "path": path.display().to_string() })));
+            for e in errors.iter() {
+                println!("  {}", e);
+            }
+            None
+        }
     }
 }
 
-fn main() {
-    // By default, run from file
-    let datadir = find_data_directory();
-    let mut game_abs_pathbuf = datadir.join("game.json");
-    if ! game_abs_pathbuf.exists() {
-        println!("game file doesn't exist! PANIC");
+/// File locations for a run, from the command line or their defaults
+struct Paths {
+    roster: PathBuf,
+    /// the base pack followed by any add-ons
+    events: Vec<PathBuf>,
+    config: PathBuf,
+    locales_dir: PathBuf,
+    images: img::ImagePaths,
+}
+
+impl Paths {
+    fn from_matches(m: &ArgMatches) -> Paths {
+        let path = |name: &str, default: &str| PathBuf::from(m.value_of(name).unwrap_or(default));
+        let default_images = img::ImagePaths::default();
+        Paths{
+            roster: path("roster", "data/roster.json"),
+            events: match m.values_of("events") {
+                Some(files) => files.map(PathBuf::from).collect(),
+                None => vec![PathBuf::from("data/events.json")],
+            },
+            config: path("config", "data/game.json"),
+            locales_dir: path("locales-dir", "data/locales"),
+            images: img::ImagePaths{
+                font: m.value_of("font").map(PathBuf::from).unwrap_or(default_images.font),
+                avatars_dir: m.value_of("avatars-dir").map(PathBuf::from).unwrap_or(default_images.avatars_dir),
+                out_dir: m.value_of("out-dir").map(PathBuf::from).unwrap_or(default_images.out_dir),
+            },
+        }
     }
+}
 
-    // now read as json
-    let game_data = fs::read_to_string(game_abs_pathbuf)
-        .expect("Something went wrong reading the file");
+fn load_settings(path: &Path, strings: &Strings) -> GameSettings {
+    if !path.exists() {
+        println!("{}", fill(&strings.settings_missing, &json!({ "path": path.display().to_string() })));
+        return GameSettings::default();
+    }
+    match GameSettings::load(path) {
+        Ok(settings) => settings,
+        Err(e) => {
+            println!("{}", fill(&strings.invalid_settings, &json!({ "error": e })));
+            process::exit(1);
+        }
+    }
+}
 
-    let mut v: Value = Value::Null;
-    match serde_json::from_str(game_data.as_str()) {
-        Ok(result) => v = result,
-        Err(e) => println!("Error {}", e)
+/// A seed on the command line wins over the one in game.json
+fn pick_seed(m: &ArgMatches, settings: &GameSettings) -> u64 {
+    if m.is_present("seed") {
+        return value_t!(m, "seed", u64).unwrap_or_else(|e| e.exit());
     }
+    settings.seed.unwrap_or_else(game::random_seed)
+}
 
-    // TODO move into its own class
-    let lconsole: u64 = v["io"]["lconsole"].as_u64().unwrap();
-    let lfile: u64 = v["io"]["lfile"].as_u64().unwrap();
-    let limages: u64 = v["io"]["limages"].as_u64().unwrap();
+/// Plays a game on `game_roster` using the io settings from the config
+///
+/// With console output on, it waits for enter before every round.
+fn play(paths: &Paths, m: &ArgMatches, game_roster: roster::Roster, render_only: bool, game_locale: &Locale) -> i32 {
+    let strings = &game_locale.strings;
+    let mut settings = load_settings(&paths.config, strings);
+    let seed = pick_seed(m, &settings);
+    if render_only {
+        settings.io.lconsole = false;
+        settings.io.limages = true;
+    }
 
-    let mut game_roster: roster::Roster = roster::Roster::new();
+    let events = match load_events(&paths.events, strings) {
+        Some(events) => events,
+        None => return 1
+    };
 
-    let mut roster_abs_pathbuf = datadir.join("roster.json");
-    if ! roster_abs_pathbuf.exists() {
-        println!("default file doesn't exist! PANIC");
+    let mut outputs = output::from_settings(&settings.io, &game_roster, &paths.images, strings);
+    let mut sim = game::Simulation::new(game_roster, events, settings.rules, seed);
+    sim.set_locale(game_locale.clone());
+    game::gameloop(&mut sim, &mut outputs, settings.io.lconsole);
+    if render_only {
+        let context = json!({ "0": seed, "path": paths.images.out_dir.display().to_string() });
+        println!("{}", fill(&strings.images_written, &context));
     }
+    0
+}
 
-    // now read as json
-    let roster_data = fs::read_to_string(roster_abs_pathbuf)
-        .expect("Something went wrong reading the file");
+/// `hunger run` and `hunger render`: play a game on the roster file
+fn run_game(paths: &Paths, m: &ArgMatches, render_only: bool, game_locale: &Locale) -> i32 {
+    match load_roster(&paths.roster, &game_locale.strings) {
+        Some(game_roster) => play(paths, m, game_roster, render_only, game_locale),
+        None => 1
+    }
+}
 
-    let mut v: Value = Value::Null;
-    match serde_json::from_str(roster_data.as_str()) {
-        Ok(result) => v = result,
-        Err(e) => println!("Error {}", e)
+/// `hunger lint [FILE]...`: report problems in the event packs and roster
+fn run_lint(paths: &Paths, m: &ArgMatches, strings: &Strings) -> i32 {
+    let events_paths: Vec<PathBuf> = match m.values_of("FILE") {
+        Some(files) => files.map(PathBuf::from).collect(),
+        None => paths.events.clone(),
+    };
+    let files: Vec<&Path> = events_paths.iter().map(PathBuf::as_path).collect();
+    let issues = lint::lint_events_files(&files);
+    for issue in issues.iter() {
+        println!("{}", issue);
     }
+    let n_errors = issues.iter().filter(|i| i.severity == lint::Severity::Error).count();
+    let names: Vec<String> = files.iter().map(|f| f.display().to_string()).collect();
+    let context = json!({ "files": names.join(" + "), "errors": n_errors, "warnings": issues.len() - n_errors });
+    println!("{}", fill(&strings.lint_summary, &context));
 
-    // now read v for number of tributes
-    let number_of_tributes = v["number_of_tributes"].as_i64().unwrap();
+    let roster_ok = m.value_of("FILE").is_some() || load_roster(&paths.roster, strings).is_some();
+    if n_errors > 0 || !roster_ok { 1 } else { 0 }
+}
 
-    println!("{}", number_of_tributes);
+/// `hunger simulate [--runs N]`: play many games headless and report win
+/// rates, kills, placements and where deaths happen
+fn run_simulate(paths: &Paths, m: &ArgMatches, strings: &Strings) -> i32 {
+    let settings = load_settings(&paths.config, strings);
+    let base_seed = pick_seed(m, &settings);
+    let runs = value_t!(m, "runs", u32).unwrap_or_else(|e| e.exit());
+    let game_roster = match load_roster(&paths.roster, strings) {
+        Some(game_roster) => game_roster,
+        None => return 1
+    };
+    let events = match load_events(&paths.events, strings) {
+        Some(events) => events,
+        None => return 1
+    };
+    println!("{}", fill(&strings.simulating, &json!({ "0": runs })));
+    let batch = stats::run_batch(&game_roster, &events, &settings.rules, runs, base_seed);
+    println!("{}", batch.report(strings));
+    0
+}
 
-    // the tributes should now be accessed correctly 
-    for i in 1..number_of_tributes+1 {
-        let test_tribute = tribute::Tribute::from_data(
-            v[format!("{}", i)]["name"].as_str().unwrap(),
-            v[format!("{}", i)]["gender"].as_str().unwrap(),
-            v[format!("{}", i)]["avatar"].as_str().unwrap());
-        game_roster.add_tribute(Box::new(test_tribute));
+/// `hunger packs`: report which pack every action of the merged events came from
+fn run_packs(paths: &Paths, strings: &Strings) -> i32 {
+    let events = match load_events(&paths.events, strings) {
+        Some(events) => events,
+        None => return 1
+    };
+    let actions = events.actions();
+    for path in paths.events.iter() {
+        let pack = events::pack_name(path);
+        let n = actions.iter().filter(|(_, a)| a.pack == pack).count();
+        println!("{}", fill(&strings.pack_actions, &json!({ "pack": pack, "0": n })));
+    }
+    println!();
+    for (location, action) in actions.iter() {
+        let id = action.id.as_ref().map(|id| format!(" [{}]", id)).unwrap_or_default();
+        println!("{:24} {:16} {}{}", location, action.pack, action.msg, id);
     }
+    0
+}
 
-    // create thumbnails before to save time
-    img::init_thumbs(&game_roster);
+/// `hunger convert IN OUT`: rewrite an event pack in the format of OUT's
+/// extension, checking nothing is lost on the way
+fn run_convert(m: &ArgMatches, strings: &Strings) -> i32 {
+    let input = Path::new(m.value_of("IN").unwrap());
+    let output = Path::new(m.value_of("OUT").unwrap());
+    let v = match events::read_pack(input) {
+        Ok(v) => v,
+        Err(e) => {
+            let context = json!({ "path": input.display().to_string(), "error": e.message });
+            println!("{}", fill(&strings.cannot_read, &context));
+            return 1;
+        }
+    };
+    let format = events::PackFormat::from_path(output);
+    let text = match format.write(&v) {
+        Ok(text) => text,
+        Err(e) => {
+            let context = json!({ "path": input.display().to_string(), "format": format.as_str(), "error": e });
+            println!("{}", fill(&strings.cannot_convert, &context));
+            return 1;
+        }
+    };
+    if format.parse(&text).ok().as_ref() != Some(&v) {
+        let context = json!({ "path": input.display().to_string(), "format": format.as_str() });
+        println!("{}", fill(&strings.lossy_conversion, &context));
+        return 1;
+    }
+    match fs::write(output, text) {
+        Ok(_) => {
+            println!("{}", fill(&strings.wrote, &json!({ "path": output.display().to_string() })));
+            0
+        }
+        Err(e) => {
+            let context = json!({ "path": output.display().to_string(), "error": e.to_string() });
+            println!("{}", fill(&strings.cannot_write, &context));
+            1
+        }
+    }
+}
 
-    let status = game::gameloop(&mut game_roster);  
+fn cli() -> App<'static, 'static> {
+    let global = |name: &'static str, value: &'static str, help: &'static str| {
+        Arg::with_name(name).long(name).value_name(value).help(help).takes_value(true).global(true)
+    };
+    App::new("hunger")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Hunger Game Simulator")
+        .setting(AppSettings::VersionlessSubcommands)
+        .arg(global("roster", "FILE", "Roster file [default: data/roster.json]"))
+        .arg(global("events", "FILE", "Event pack, repeat to add packs on top [default: data/events.json]")
+            .multiple(true).number_of_values(1))
+        .arg(global("config", "FILE", "Game settings [default: data/game.json]"))
+        .arg(global("font", "FILE", "Font used for images [default: fonts/Roboto-Regular.ttf]"))
+        .arg(global("avatars-dir", "DIR", "Directory holding the roster avatars [default: input]"))
+        .arg(global("out-dir", "DIR", "Directory for images, thumbnails and logs [default: output]"))
+        .arg(global("locale", "CODE", "Language for the game, e.g. de [default: en]"))
+        .arg(global("locales-dir", "DIR", "Directory holding the locale files [default: data/locales]"))
+        .arg(global("seed", "N", "Seed for the game, overriding game.json"))
+        .subcommand(SubCommand::with_name("run")
+            .about("Play a game from the roster and event pack (the default)"))
+        .subcommand(SubCommand::with_name("interactive")
+            .about("Build a roster interactively, then play it"))
+        .subcommand(SubCommand::with_name("render")
+            .about("Play a game straight to images, without pausing or console output"))
+        .subcommand(SubCommand::with_name("lint")
+            .alias("lint-events")
+            .about("Check the event pack and roster for mistakes")
+            .arg(Arg::with_name("FILE").help("Event packs to check instead of --events").index(1).multiple(true)))
+        .subcommand(SubCommand::with_name("convert")
+            .about("Convert an event pack between JSON, TOML and YAML, by file extension")
+            .arg(Arg::with_name("IN").help("Event pack to read").required(true).index(1))
+            .arg(Arg::with_name("OUT").help("File to write, ending in .json, .toml, .yaml or .yml").required(true).index(2)))
+        .subcommand(SubCommand::with_name("packs")
+            .about("List which event pack every action comes from"))
+        .subcommand(SubCommand::with_name("simulate")
+            .about("Play many games headless and report balance statistics")
+            .arg(Arg::with_name("runs").long("runs").value_name("N").takes_value(true)
+                .default_value("1000").help("Number of games to play")))
+}
+
+fn main() {
+    let matches = cli().get_matches();
+    let (name, sub) = matches.subcommand();
+    let m = sub.unwrap_or(&matches);
+    let paths = Paths::from_matches(m);
+    let code = m.value_of("locale").unwrap_or(locale::DEFAULT_LOCALE);
+    let game_locale = match Locale::load(&paths.locales_dir, code) {
+        Ok(game_locale) => game_locale,
+        Err(e) => {
+            // no locale to word this in
+            println!("invalid locale: {}", e);
+            process::exit(1);
+        }
+    };
+    let strings = &game_locale.strings;
+
+    let status = match name {
+        "interactive" => run_interactive(&paths, m, &game_locale),
+        "render" => run_game(&paths, m, true, &game_locale),
+        "lint" => run_lint(&paths, m, strings),
+        "simulate" => run_simulate(&paths, m, strings),
+        "packs" => run_packs(&paths, strings),
+        "convert" => run_convert(m, strings),
+        _ => run_game(&paths, m, false, &game_locale),
+    };
+    process::exit(status);
 }
@@ -0,0 +1,105 @@
+use serde_json::json;
+
+use super::roster;
+use super::img;
+use super::game::{Simulation, RoundResult, RoundType};
+use super::log::GameLog;
+use super::settings::IoSettings;
+use super::locale::{Strings, fill};
+
+/// A destination for a running game, e.g. the console, PNGs or a log file
+///
+/// `gameloop` hands every sink the game as it starts, each round as it is
+/// played and the final state once a winner is decided.
+pub trait Output {
+    fn start(&mut self, _sim: &Simulation) {}
+    fn round(&mut self, sim: &Simulation, round: &RoundResult);
+    fn finish(&mut self, _sim: &Simulation) {}
+}
+
+/// Prints rounds and the final summary to stdout
+pub struct ConsoleOutput;
+
+impl Output for ConsoleOutput {
+    fn start(&mut self, sim: &Simulation) {
+        println!("{}", fill(&sim.locale().strings.seed, &json!({ "0": sim.seed() })));
+    }
+    fn round(&mut self, sim: &Simulation, round: &RoundResult) {
+        println!("{}", round.title);
+        if round.round_type == RoundType::FALLEN && !round.members.is_empty() {
+            println!("{}", sim.roster().death_summary(&round.members, &sim.locale().strings));
+        }
+        for action in round.actions.iter() {
+            println!("{}", action.message);
+        }
+    }
+    fn finish(&mut self, sim: &Simulation) {
+        println!("{}", sim.roster().game_summary(&sim.locale().strings));
+    }
+}
+
+/// Renders the round title and every action to `<out_dir>/hgNNN.png`
+pub struct ImageOutput {
+    imgidx: u32,
+    paths: img::ImagePaths,
+}
+
+impl ImageOutput {
+    /// Prepares the avatar thumbnails up front to save time during the game
+    pub fn new(game_roster: &roster::Roster, paths: &img::ImagePaths) -> ImageOutput {
+        img::init_thumbs(game_roster, paths);
+        ImageOutput{imgidx:0, paths:paths.clone()}
+    }
+}
+
+impl Output for ImageOutput {
+    fn round(&mut self, sim: &Simulation, round: &RoundResult) {
+        img::image(round.title.clone(), sim.roster(), &round.members, &self.imgidx, &self.paths);
+        self.imgidx += 1;
+        for action in round.actions.iter() {
+            img::image(action.message.clone(), sim.roster(), &action.members, &self.imgidx, &self.paths);
+            self.imgidx += 1;
+        }
+    }
+}
+
+impl Output for GameLog {
+    fn start(&mut self, sim: &Simulation) {
+        if let Err(e) = self.write_start(sim.roster(), sim.seed()) {
+            println!("{}", fill(&sim.locale().strings.log_error, &json!({ "0": e.to_string() })));
+        }
+    }
+    fn round(&mut self, sim: &Simulation, round: &RoundResult) {
+        if let Err(e) = self.write_round(sim.roster(), round) {
+            println!("{}", fill(&sim.locale().strings.log_error, &json!({ "0": e.to_string() })));
+        }
+    }
+    fn finish(&mut self, sim: &Simulation) {
+        if let Err(e) = self.write_summary(sim.roster(), sim.day()) {
+            println!("{}", fill(&sim.locale().strings.log_error, &json!({ "0": e.to_string() })));
+        }
+    }
+}
+
+/// Builds the sinks enabled by the `io` settings; the game log is written to
+/// `game.jsonl` next to the images
+pub fn from_settings(io: &IoSettings, game_roster: &roster::Roster, paths: &img::ImagePaths, strings: &Strings) -> Vec<Box<dyn Output>> {
+    let log_path = paths.out_dir.join("game.jsonl");
+    let mut outputs: Vec<Box<dyn Output>> = Vec::new();
+    if io.lconsole {
+        outputs.push(Box::new(ConsoleOutput));
+    }
+    if io.limages {
+        outputs.push(Box::new(ImageOutput::new(game_roster, paths)));
+    }
+    if io.lfile {
+        match GameLog::create(&log_path) {
+            Ok(game_log) => outputs.push(Box::new(game_log)),
+            Err(e) => {
+                let context = json!({ "path": log_path.display().to_string(), "error": e.to_string() });
+                println!("{}", fill(&strings.cannot_write_log, &context));
+            }
+        }
+    }
+    outputs
+}
@@ -1,11 +1,23 @@
 use super::tribute;
+use super::locale::{Locale, Strings};
 
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use rand::Rng;
+use rand::seq::SliceRandom;
 use serde::ser::{Serialize, Serializer, SerializeMap};
 use serde_json::Value;
 use serde_json::json;
 
+#[derive(Clone, Debug)]
 pub struct Roster {
-    tribute_vec: Vec<Box<tribute::Tribute>>,
+    tribute_vec: Vec<tribute::Tribute>,
+    /// allied pairs of roster indices, smaller index first
+    alliances: BTreeSet<(usize, usize)>,
 }
 
 impl Serialize for Roster {
@@ -25,28 +37,242 @@ impl Serialize for Roster {
     }
 }
 
+/// A problem found while loading a roster file
+///
+/// `location` is the array index (`[2]`) or legacy key (`"3"`) of the entry.
+#[derive(Clone, Debug)]
+pub struct RosterError {
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for RosterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.location.is_empty() {
+            write!(f, "{}", self.message)
+        }
+        else {
+            write!(f, "{}: {}", self.location, self.message)
+        }
+    }
+}
+
+impl RosterError {
+    fn new(location: &str, message: String) -> RosterError {
+        RosterError{location:location.to_string(), message}
+    }
+}
+
+/// Splits a roster file into its raw tribute entries, each with a location
+///
+/// Accepts either a plain array of tributes or the legacy object layout of
+/// `number_of_tributes` plus string keys "1".."N".
+fn raw_entries(v: &Value, errors: &mut Vec<RosterError>) -> Vec<(String, Value)> {
+    match v {
+        Value::Array(list) => {
+            list.iter().enumerate()
+                .map(|(i, t)| (format!("[{}]", i), t.clone()))
+                .collect()
+        }
+        Value::Object(map) => {
+            let mut numbered: Vec<(usize, Value)> = Vec::new();
+            for (key, t) in map.iter() {
+                if key == "number_of_tributes" {
+                    continue;
+                }
+                match key.parse::<usize>() {
+                    Ok(n) if n >= 1 => numbered.push((n, t.clone())),
+                    _ => errors.push(RosterError::new(&format!("\"{}\"", key), String::from("unexpected key"))),
+                }
+            }
+            numbered.sort_by_key(|(n, _)| *n);
+
+            match map.get("number_of_tributes") {
+                Some(count) => match count.as_u64() {
+                    Some(count) => {
+                        let count = count as usize;
+                        for i in 1..=count {
+                            if !numbered.iter().any(|(n, _)| *n == i) {
+                                errors.push(RosterError::new(&format!("\"{}\"", i), format!(
+                                    "missing, number_of_tributes is {}", count)));
+                            }
+                        }
+                        for (n, _) in numbered.iter().filter(|(n, _)| *n > count) {
+                            errors.push(RosterError::new(&format!("\"{}\"", n), format!(
+                                "beyond number_of_tributes ({})", count)));
+                        }
+                    }
+                    None => errors.push(RosterError::new("number_of_tributes", format!(
+                        "expected a non-negative integer, found {}", count))),
+                },
+                None => {
+                    for (i, (n, _)) in numbered.iter().enumerate() {
+                        if *n != i + 1 {
+                            errors.push(RosterError::new(&format!("\"{}\"", i + 1), String::from("missing")));
+                            break;
+                        }
+                    }
+                }
+            }
+
+            numbered.into_iter()
+                .map(|(n, t)| (format!("\"{}\"", n), t))
+                .collect()
+        }
+        _ => {
+            errors.push(RosterError::new("", String::from("a roster must be an array of tributes")));
+            Vec::new()
+        }
+    }
+}
+
+fn check_entry(location: &str, entry: &tribute::TributeEntry, errors: &mut Vec<RosterError>) {
+    if entry.name.trim().is_empty() {
+        errors.push(RosterError::new(location, String::from("name is empty")));
+    }
+    if entry.gender.parse::<tribute::Gender>().is_err() {
+        errors.push(RosterError::new(location, format!("unknown gender `{}` (expected M, F or A)", entry.gender)));
+    }
+    if let Some(avatar) = &entry.avatar {
+        if avatar.trim().is_empty() {
+            errors.push(RosterError::new(location, String::from("avatar is empty, leave it out instead")));
+        }
+    }
+    if entry.district == Some(0) {
+        errors.push(RosterError::new(location, String::from("districts are numbered from 1")));
+    }
+    if let Some(p) = &entry.pronouns {
+        for (form, value) in [("nominative", &p.nominative), ("accusative", &p.accusative),
+                ("genitive", &p.genitive), ("reflexive", &p.reflexive)].iter() {
+            if value.trim().is_empty() {
+                errors.push(RosterError::new(location, format!("pronoun `{}` is empty", form)));
+            }
+        }
+    }
+    if entry.inventory.iter().any(|tag| tag.trim().is_empty()) {
+        errors.push(RosterError::new(location, String::from("inventory contains an empty item tag")));
+    }
+    if let Some(stats) = &entry.stats {
+        for (stat, value) in [("strength", stats.strength), ("agility", stats.agility),
+                ("wits", stats.wits), ("luck", stats.luck)].iter() {
+            if *value < tribute::STAT_MIN || *value > tribute::STAT_MAX {
+                errors.push(RosterError::new(location, format!("{} {} is outside {}-{}",
+                    stat, value, tribute::STAT_MIN, tribute::STAT_MAX)));
+            }
+        }
+    }
+}
+
+impl fmt::Display for Roster {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, item) in self.tribute_vec.iter().enumerate() {
+            writeln!(f, "({}): {}", i, item)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Roster {
+    fn default() -> Roster {
+        Roster::new()
+    }
+}
+
+/// Parses and validates a roster from JSON text, like `Roster::load`
+impl FromStr for Roster {
+    type Err = Vec<RosterError>;
+
+    fn from_str(data: &str) -> Result<Roster, Vec<RosterError>> {
+        let v: Value = match serde_json::from_str(data) {
+            Ok(v) => v,
+            Err(e) => return Err(vec![RosterError::new("", e.to_string())]),
+        };
+        Roster::from_value(&v)
+    }
+}
+
+/// Fills in a tribute's pronoun labels from their own set, or from the
+/// locale's set for their gender when they have none
+fn set_gender_labels(item: &mut tribute::Tribute, locale: &Locale) {
+    let p = item.pronouns.clone().unwrap_or_else(|| locale.pronouns_for(&item.gender));
+    item.gender_label_nominative = p.nominative;
+    item.gender_label_accusative = p.accusative;
+    item.gender_label_genitive = p.genitive;
+    item.gender_label_reflexitive = p.reflexive;
+    item.plural_verbs = p.plural;
+}
+
 impl Roster {
     // static constructor
     pub fn new() -> Roster {
         // initialize empty array
-        let tempvec = Vec::<Box<tribute::Tribute>>::new();
-        Roster{tribute_vec:tempvec}
+        let tempvec = Vec::<tribute::Tribute>::new();
+        Roster{tribute_vec:tempvec, alliances:BTreeSet::new()}
     }
-    pub fn add_tribute(&mut self, tb: Box<tribute::Tribute>) {
-        self.tribute_vec.push(tb);
+    /// Reads and validates a roster file, reporting every problem at once
+    pub fn load(path: &Path) -> Result<Roster, Vec<RosterError>> {
+        let data = match fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) => return Err(vec![RosterError::new(&path.display().to_string(), e.to_string())]),
+        };
+        data.parse()
     }
-    pub fn to_string(&self) -> String {
-        let mut output: String = String::from("");
-        for (i, item) in self.tribute_vec.iter().enumerate() {
-            let tb: tribute::Tribute = *item.clone();
-            let tb_contents: String = tb.to_string();
-            output = format!("{}({}): {}\n", output, i, tb_contents);
+    pub fn from_value(v: &Value) -> Result<Roster, Vec<RosterError>> {
+        let mut errors: Vec<RosterError> = Vec::new();
+        let mut names: HashSet<String> = HashSet::new();
+        let mut roster = Roster::new();
+
+        for (location, raw) in raw_entries(v, &mut errors) {
+            let entry: tribute::TributeEntry = match serde_json::from_value(raw) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    errors.push(RosterError::new(&location, e.to_string()));
+                    continue;
+                }
+            };
+            check_entry(&location, &entry, &mut errors);
+            if !names.insert(entry.name.clone()) {
+                errors.push(RosterError::new(&location, format!("duplicate name `{}`", entry.name)));
+            }
+            roster.add_tribute(tribute::Tribute::from_entry(&entry));
         }
-        output
+
+        // keep district partners next to each other, districtless tributes last
+        roster.tribute_vec.sort_by_key(|tb| tb.district.unwrap_or(u32::MAX));
+
+        if errors.is_empty() && roster.len() < 2 {
+            errors.push(RosterError::new("", format!("a game needs at least 2 tributes, found {}", roster.len())));
+        }
+        if errors.is_empty() { Ok(roster) } else { Err(errors) }
+    }
+    /// Writes the roster in the array file format
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let entries: Vec<tribute::TributeEntry> = self.tribute_vec.iter().map(|tb| tb.to_entry()).collect();
+        let data = serde_json::to_string_pretty(&entries)?;
+        fs::write(path, data + "\n")
+    }
+    pub fn add_tribute(&mut self, tb: tribute::Tribute) {
+        self.tribute_vec.push(tb);
+    }
+    pub fn remove_tribute(&mut self, i: usize) -> tribute::Tribute {
+        // indices shift down, so pairs have to be renumbered
+        let shift = |j: usize| if j > i { j - 1 } else { j };
+        self.alliances = self.alliances.iter()
+            .filter(|&&(a, b)| a != i && b != i)
+            .map(|&(a, b)| (shift(a), shift(b)))
+            .collect();
+        self.tribute_vec.remove(i)
+    }
+    pub fn shuffle<R: Rng>(&mut self, rng: &mut R) {
+        self.tribute_vec.shuffle(rng);
+        self.alliances.clear();
     }
     pub fn len(&self) -> usize {
         self.tribute_vec.len()
     }
+    pub fn is_empty(&self) -> bool {
+        self.tribute_vec.is_empty()
+    }
     pub fn n_alive(&self) -> i32 {
         let mut n: i32 = 0;
         for item in self.tribute_vec.iter() {
@@ -65,15 +291,18 @@ impl Roster {
         }
         n
     }
-    pub fn get_alive(&self, i: usize) -> bool {
-        self.tribute_vec[i].alive
-    }
     pub fn get_available(&self, i: usize) -> bool {
         self.tribute_vec[i].available
     }
-    pub fn get_name(&self, i: usize) -> String {
-        let s: String = self.tribute_vec[i].name.clone();
-        s
+    pub fn get_tribute(&self, i: usize) -> &tribute::Tribute {
+        &self.tribute_vec[i]
+    }
+    pub fn get_tribute_mut(&mut self, i: usize) -> &mut tribute::Tribute {
+        &mut self.tribute_vec[i]
+    }
+    /// The tribute called `name`, ignoring case
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.tribute_vec.iter().position(|tb| tb.name.eq_ignore_ascii_case(name))
     }
     pub fn get_avatar(&self, i: usize) -> Option<String> {
         let o: Option<String> = self.tribute_vec[i].avatar.clone();
@@ -99,14 +328,46 @@ impl Roster {
     pub fn set_unavailable(&mut self, i: usize) {
         self.tribute_vec[i].available = false;
     }
+    pub fn set_available(&mut self, i: usize) {
+        self.tribute_vec[i].available = self.tribute_vec[i].alive;
+    }
+    /// True when both tributes come from the same district
+    pub fn same_district(&self, a: usize, b: usize) -> bool {
+        match (self.tribute_vec[a].district, self.tribute_vec[b].district) {
+            (Some(da), Some(db)) => da == db,
+            _ => false,
+        }
+    }
+    pub fn ally(&mut self, a: usize, b: usize) {
+        if a != b {
+            self.alliances.insert((a.min(b), a.max(b)));
+        }
+    }
+    pub fn break_alliance(&mut self, a: usize, b: usize) {
+        self.alliances.remove(&(a.min(b), a.max(b)));
+    }
+    pub fn are_allies(&self, a: usize, b: usize) -> bool {
+        self.alliances.contains(&(a.min(b), a.max(b)))
+    }
+    /// Roster indices grouped by district, in district order
+    pub fn districts(&self) -> BTreeMap<u32, Vec<usize>> {
+        let mut districts: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+        for (idx, item) in self.tribute_vec.iter().enumerate() {
+            if let Some(d) = item.district {
+                districts.entry(d).or_default().push(idx);
+            }
+        }
+        districts
+    }
+    /// Lifts a status from every tribute, e.g. `Hidden` when a new day begins
+    pub fn cure_all(&mut self, status: tribute::Status) {
+        for item in self.tribute_vec.iter_mut() {
+            item.cure_status(status);
+        }
+    }
     pub fn activate(&mut self) {
         for item in self.tribute_vec.iter_mut() {
-            if item.alive {
-                item.available = true
-            }
-            else {
-                item.available = false
-            }
+            item.available = item.alive;
         }
     }
     pub fn count_dead_on_day(&self, day: i32) -> i32 {
@@ -118,56 +379,143 @@ impl Roster {
         }
         n
     }
-    pub fn death_summary_on_day(&self, day: i32) -> String {
-        let mut output: String = String::from("The following tributes have died today: \n");
-        for item in self.tribute_vec.iter() {
-            if item.deathday == day {
-                output = format!("{}{}\n", output, item.name);
-            }
+    pub fn death_summary_on_day(&self, day: i32, strings: &Strings) -> String {
+        self.death_summary(&self.get_dead_indices(day), strings)
+    }
+    pub fn death_summary(&self, dead: &[usize], strings: &Strings) -> String {
+        let mut output: String = format!("{}\n", strings.died_today);
+        for &i in dead.iter() {
+            output = format!("{}{}\n", output, self.tribute_vec[i].name);
         }
         output
     }
-    pub fn default_gender_setup(&mut self) {
+    /// Fills in every tribute's pronoun labels, see `set_gender_labels`
+    pub fn default_gender_setup(&mut self, locale: &Locale) {
         for item in self.tribute_vec.iter_mut() {
-            match item.gender {
-                tribute::Gender::M => {
-                    item.gender_label_nominative = String::from("he");
-                    item.gender_label_accusative = String::from("him");
-                    item.gender_label_genitive = String::from("his");
-                    item.gender_label_reflexitive = String::from("himself");
-                }
-                tribute::Gender::F => {
-                    item.gender_label_nominative = String::from("she");
-                    item.gender_label_accusative = String::from("her");
-                    item.gender_label_genitive = String::from("her");
-                    item.gender_label_reflexitive = String::from("herself");
-                }
-                tribute::Gender::A => {
-                    item.gender_label_nominative = String::from("they");
-                    item.gender_label_accusative = String::from("them");
-                    item.gender_label_genitive = String::from("their");
-                    item.gender_label_reflexitive = String::from("themselves");
-                }
-            }
+            set_gender_labels(item, locale);
         }
     }
     pub fn serialize_tribute(&self, i: usize) -> Value {
         json!(self.tribute_vec[i])
     }
-    pub fn game_summary(&self) -> String {
-        let mut output: String = String::from("Simulation Complete: \n");
-        output = format!("{}Name                 Kills   Died    \n", output);
-        output = format!("{}-------------------------------------\n", output);
+    /// `serialize_tribute`, with `locale`'s pronoun labels when given, for
+    /// text translated into it
+    pub fn serialize_tribute_in(&self, i: usize, locale: Option<&Locale>) -> Value {
+        match locale {
+            Some(locale) => {
+                let mut item = self.tribute_vec[i].clone();
+                set_gender_labels(&mut item, locale);
+                json!(item)
+            }
+            None => self.serialize_tribute(i),
+        }
+    }
+    pub fn game_summary(&self, strings: &Strings) -> String {
+        let districts = self.districts();
+        let header = if districts.is_empty() {
+            format!("{:20} {:7} {:8}", strings.name, strings.kills, strings.died)
+        }
+        else {
+            format!("{:20} {:8} {:7} {:8}", strings.name, strings.district, strings.kills, strings.died)
+        };
+        let mut output: String = format!("{}\n{}\n{}\n", strings.game_complete, header,
+            "-".repeat(header.chars().count()));
         for item in self.tribute_vec.iter() {
             let mut died = item.deathday.to_string();
-            if died == "0".to_string() {
-                died = "Survivor".to_string();
+            if died == "0" {
+                died = strings.survivor.clone();
+            }
+            let district = match (districts.is_empty(), item.district) {
+                (true, _) => String::new(),
+                (false, Some(d)) => format!("{:<8} ", d),
+                (false, None) => format!("{:8} ", "-"),
+            };
+            output = format!("{}{:20} {}{:7} {:7}\n", output,
+                item.name, district, item.killcount.to_string(), died);
+        }
+        if !districts.is_empty() {
+            let header = format!("{:8} {:7} {:9}", strings.district, strings.kills, strings.survivors);
+            output = format!("{}\n{}\n{}\n", output, header, "-".repeat(header.chars().count()));
+            for (d, members) in districts.iter() {
+                let kills: i32 = members.iter().map(|&i| self.tribute_vec[i].killcount).sum();
+                let survivors = members.iter().filter(|&&i| self.tribute_vec[i].alive).count();
+                output = format!("{}{:<8} {:<7} {:<9}\n", output, d, kills, survivors);
             }
-            output = format!("{}{:20} {:7} {:7}\n", output,
-                item.name, item.killcount.to_string(), died);
         }
         output
     }
 }
 
- 
\ No newline at end of file
+ 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(game_roster: &Roster) -> Vec<&str> {
+        game_roster.tribute_vec.iter().map(|tb| tb.name.as_str()).collect()
+    }
+
+    fn errors(data: &str) -> Vec<String> {
+        data.parse::<Roster>().unwrap_err().iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn legacy_layout_is_read_in_key_order() {
+        let game_roster: Roster = r#"{
+            "number_of_tributes": 3,
+            "2": {"name": "Dan", "gender": "M", "avatar": "avatar2.png"},
+            "1": {"name": "Anne", "gender": "F"},
+            "3": {"name": "Sam"}
+        }"#.parse().unwrap();
+        assert_eq!(names(&game_roster), vec!["Anne", "Dan", "Sam"]);
+        assert_eq!(game_roster.get_avatar(1).as_deref(), Some("avatar2.png"));
+    }
+
+    #[test]
+    fn array_layout_keeps_district_partners_together() {
+        let game_roster: Roster = r#"[
+            {"name": "Anne", "district": 2}, {"name": "Dan"}, {"name": "Emma", "district": 1}, {"name": "John", "district": 2}
+        ]"#.parse().unwrap();
+        assert_eq!(names(&game_roster), vec!["Emma", "Anne", "John", "Dan"]);
+    }
+
+    #[test]
+    fn every_legacy_problem_is_reported_at_once() {
+        let errors = errors(r#"{
+            "number_of_tributes": 3,
+            "1": {"name": "Anne", "gender": "X"},
+            "2": {"name": "Anne"},
+            "4": {"name": "Dan"},
+            "extra": {}
+        }"#);
+        assert_eq!(errors, vec![
+            "\"extra\": unexpected key",
+            "\"3\": missing, number_of_tributes is 3",
+            "\"4\": beyond number_of_tributes (3)",
+            "\"1\": unknown gender `X` (expected M, F or A)",
+            "\"2\": duplicate name `Anne`",
+        ]);
+    }
+
+    #[test]
+    fn every_entry_problem_is_reported_at_once() {
+        let errors = errors(r#"[
+            {"name": " ", "district": 0},
+            {"name": "Anne", "stats": {"luck": 11}, "inventory": [""]},
+            {"name": "Anne", "strength": 9},
+            {"gender": "M"}
+        ]"#);
+        assert_eq!(errors.len(), 6, "{:?}", errors);
+        assert_eq!(errors[0], "[0]: name is empty");
+        assert_eq!(errors[1], "[0]: districts are numbered from 1");
+        assert_eq!(errors[2], "[1]: inventory contains an empty item tag");
+        assert_eq!(errors[3], "[1]: luck 11 is outside 1-10");
+        assert!(errors[4].starts_with("[2]: unknown field `strength`"));
+        assert!(errors[5].starts_with("[3]: missing field `name`"));
+    }
+
+    #[test]
+    fn a_game_needs_two_tributes() {
+        assert_eq!(errors(r#"[{"name": "Anne"}]"#), vec!["a game needs at least 2 tributes, found 1"]);
+    }
+}
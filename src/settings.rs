@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Deserializer};
+
+use super::tribute::Status;
+
+/// Accepts `true`/`false` as well as the legacy `1`/`0` flags
+fn flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Bool(bool),
+        Int(u64),
+    }
+    match Flag::deserialize(deserializer)? {
+        Flag::Bool(b) => Ok(b),
+        Flag::Int(i) => Ok(i != 0),
+    }
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// The `io` block of `game.json`: which outputs a run produces
+#[derive(Clone, Debug, Deserialize)]
+pub struct IoSettings {
+    /// print rounds to the console
+    #[serde(default = "enabled", deserialize_with = "flag")]
+    pub lconsole: bool,
+    /// write the JSON Lines game log
+    #[serde(default, deserialize_with = "flag")]
+    pub lfile: bool,
+    /// render every round to a PNG
+    #[serde(default = "enabled", deserialize_with = "flag")]
+    pub limages: bool,
+}
+
+impl Default for IoSettings {
+    fn default() -> IoSettings {
+        IoSettings{lconsole:true, lfile:false, limages:true}
+    }
+}
+
+/// Extra fatality added to rounds of each type
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FatalityBonus {
+    pub bloodbath: i32,
+    pub feast: i32,
+    pub arena: i32,
+    pub day: i32,
+    pub night: i32,
+}
+
+impl Default for FatalityBonus {
+    fn default() -> FatalityBonus {
+        FatalityBonus{bloodbath:2, feast:2, arena:1, day:0, night:0}
+    }
+}
+
+/// The `rules.pacing` block of `game.json`: how often special rounds happen
+/// and how deadly rounds are
+///
+/// Every action slot in a round is fatal when a roll of 0-9 falls below the
+/// fatality factor: a random value from `fatality_min` to `fatality_max`,
+/// plus `drought_bonus` for each FALLEN round in a row without deaths, plus
+/// the bonus for the round type. The defaults are the original pacing.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Pacing {
+    /// feast chance in percent is `feast_base + feast_growth * d^2`, with `d`
+    /// the days since the last feast or arena event
+    pub feast_base: f64,
+    pub feast_growth: f64,
+    /// allow more than one feast per game
+    pub feast_repeats: bool,
+    /// an arena event happens on 1 in `arena_odds` rounds
+    pub arena_odds: u32,
+    pub fatality_min: i32,
+    pub fatality_max: i32,
+    pub drought_bonus: i32,
+    pub fatality_bonus: FatalityBonus,
+    /// also report the dead in a FALLEN round after each night
+    pub fallen_after_night: bool,
+}
+
+impl Default for Pacing {
+    fn default() -> Pacing {
+        Pacing{feast_base:9.0 / 55.0, feast_growth:100.0 / 55.0, feast_repeats:true, arena_odds:19,
+            fatality_min:2, fatality_max:3, drought_bonus:1, fatality_bonus:FatalityBonus::default(),
+            fallen_after_night:false}
+    }
+}
+
+impl Pacing {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.feast_base < 0.0 || self.feast_growth < 0.0 {
+            return Err(String::from("rules.pacing.feast_base and feast_growth must not be negative"));
+        }
+        if self.arena_odds == 0 {
+            return Err(String::from("rules.pacing.arena_odds must be at least 1"));
+        }
+        if self.fatality_min > self.fatality_max {
+            return Err(format!("rules.pacing.fatality_min {} is above fatality_max {}",
+                self.fatality_min, self.fatality_max));
+        }
+        Ok(())
+    }
+}
+
+/// Something spectators can send a tribute, e.g. `sponsor Anne medicine`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Gift {
+    pub cost: u32,
+    /// item tags added to the recipient's inventory
+    #[serde(default)]
+    pub gives: Vec<String>,
+    #[serde(default)]
+    pub cures: Vec<Status>,
+    /// template for the parachute message, `{gift}` being the gift's name;
+    /// the locale's `parachute` string when left out
+    #[serde(default)]
+    pub msg: Option<String>,
+}
+
+impl Gift {
+    fn new(cost: u32, gives: &[&str], cures: &[Status]) -> Gift {
+        Gift{cost, gives:gives.iter().map(|g| g.to_string()).collect(), cures:cures.to_vec(), msg:None}
+    }
+}
+
+/// The `rules.sponsors` block of `game.json`: what spectators can afford to
+/// send during an interactive game
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sponsors {
+    pub budget: u32,
+    pub gifts: BTreeMap<String, Gift>,
+}
+
+impl Default for Sponsors {
+    fn default() -> Sponsors {
+        let mut gifts: BTreeMap<String, Gift> = BTreeMap::new();
+        gifts.insert(String::from("medicine"),
+            Gift::new(40, &["medicine"], &[Status::Wounded, Status::Sick, Status::Poisoned]));
+        gifts.insert(String::from("food"), Gift::new(20, &["food"], &[Status::Starving]));
+        gifts.insert(String::from("bow"), Gift::new(50, &["bow"], &[]));
+        gifts.insert(String::from("sword"), Gift::new(50, &["sword"], &[]));
+        Sponsors{budget:100, gifts}
+    }
+}
+
+/// The `rules` block of `game.json`: knobs that change how games play out
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rules {
+    /// chance, from 0 up to but not including 1, that a fatal action pitting
+    /// district partners against each other is redrawn
+    pub district_loyalty: f64,
+    /// the game ends after this day even with several tributes left, who all
+    /// survive; keeps packs whose fatal actions run out from going on forever
+    pub max_days: i32,
+    pub pacing: Pacing,
+    pub sponsors: Sponsors,
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules{district_loyalty:0.0, max_days:100, pacing:Pacing::default(), sponsors:Sponsors::default()}
+    }
+}
+
+impl Rules {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..1.0).contains(&self.district_loyalty) {
+            return Err(format!("rules.district_loyalty must be at least 0 and below 1, found {}",
+                self.district_loyalty));
+        }
+        if self.max_days < 1 {
+            return Err(format!("rules.max_days must be at least 1, found {}", self.max_days));
+        }
+        self.pacing.validate()
+    }
+}
+
+/// Settings read from `game.json`
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct GameSettings {
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub io: IoSettings,
+    #[serde(default)]
+    pub rules: Rules,
+}
+
+impl GameSettings {
+    pub fn load(path: &Path) -> Result<GameSettings, String> {
+        let data = fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let settings: GameSettings = serde_json::from_str(&data)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        settings.rules.validate()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(settings)
+    }
+}
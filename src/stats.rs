@@ -0,0 +1,162 @@
+use serde_json::json;
+
+use super::roster;
+use super::events::EventSet;
+use super::game::{Simulation, RoundType};
+use super::settings::Rules;
+use super::locale::{Strings, fill};
+
+/// Round types that can kill, in the order they are reported
+const DEADLY_ROUNDS: &[RoundType] = &[
+    RoundType::BLOODBATH, RoundType::FEAST, RoundType::ARENA, RoundType::DAY, RoundType::NIGHT, RoundType::FALLEN];
+
+/// Running totals for one tribute across a batch
+#[derive(Clone, Debug, Default)]
+pub struct TributeStats {
+    pub name: String,
+    pub wins: u32,
+    pub kills: u64,
+    pub placement: u64,
+}
+
+/// Aggregated results of many headless games on the same roster and events
+#[derive(Clone, Debug)]
+pub struct BatchStats {
+    pub runs: u32,
+    pub base_seed: u64,
+    pub total_days: u64,
+    /// games stopped by `rules.max_days` with several tributes alive, which
+    /// count as a win for no one
+    pub capped: u32,
+    pub tributes: Vec<TributeStats>,
+    /// deaths per round name, the `DEADLY_ROUNDS` first and then custom
+    /// rounds in the order they were first seen
+    pub deaths_by_round: Vec<(String, u64)>,
+}
+
+impl BatchStats {
+    pub fn new(game_roster: &roster::Roster, base_seed: u64) -> BatchStats {
+        let tributes = (0..game_roster.len())
+            .map(|i| TributeStats{name:game_roster.get_tribute(i).name.clone(), ..TributeStats::default()})
+            .collect();
+        let deaths_by_round = DEADLY_ROUNDS.iter().map(|r| (r.as_str().to_string(), 0)).collect();
+        BatchStats{runs:0, base_seed, total_days:0, capped:0, tributes, deaths_by_round}
+    }
+
+    /// Plays one game to completion and adds it to the totals
+    pub fn record(&mut self, sim: &mut Simulation) {
+        let n = sim.roster().len();
+        let mut placement: Vec<usize> = vec![1; n];
+        let mut n_alive = sim.roster().n_alive() as usize;
+
+        while let Some(round) = sim.step() {
+            for action in round.actions.iter() {
+                if action.killed.is_empty() {
+                    continue;
+                }
+                // tributes killed together share the best of their places
+                let place = n_alive - action.killed.len() + 1;
+                for &kd in action.killed.iter() {
+                    placement[kd] = place;
+                }
+                n_alive -= action.killed.len();
+                match self.deaths_by_round.iter_mut().find(|(name, _)| *name == round.name) {
+                    Some((_, deaths)) => *deaths += action.killed.len() as u64,
+                    None => self.deaths_by_round.push((round.name.clone(), action.killed.len() as u64)),
+                }
+            }
+        }
+
+        let game_roster = sim.roster();
+        let won = game_roster.n_alive() == 1;
+        for (i, stats) in self.tributes.iter_mut().enumerate() {
+            let tb = game_roster.get_tribute(i);
+            stats.kills += tb.killcount as u64;
+            stats.placement += placement[i] as u64;
+            if won && tb.alive {
+                stats.wins += 1;
+            }
+        }
+        if !won {
+            self.capped += 1;
+        }
+        self.total_days += sim.day() as u64;
+        self.runs += 1;
+    }
+
+    /// The win rates, kills, placements and deaths per round type as a table
+    pub fn report(&self, strings: &Strings) -> String {
+        let text = &strings.batch;
+        let runs = self.runs.max(1) as f64;
+        let seeds = json!({ "runs": self.runs, "first": self.base_seed,
+            "last": self.base_seed.wrapping_add(self.runs as u64) });
+        let mut lines: Vec<String> = vec![
+            fill(&text.batch_complete, &seeds),
+            fill(&text.average_length, &json!({ "0": format!("{:.2}", self.total_days as f64 / runs) })),
+        ];
+        if self.capped > 0 {
+            let share = format!("{:.2}", 100.0 * self.capped as f64 / runs);
+            lines.push(fill(&text.capped, &json!({ "runs": self.capped, "share": share })));
+        }
+        lines.push(String::new());
+        let header = format!("{:20} {:7} {:7} {:8}", strings.name, text.win_rate, strings.kills, text.placement);
+        lines.push(header.clone());
+        lines.push("-".repeat(header.chars().count()));
+        let mut order: Vec<&TributeStats> = self.tributes.iter().collect();
+        order.sort_by_key(|t| std::cmp::Reverse(t.wins));
+        for t in order {
+            lines.push(format!("{:20} {:7.2} {:7.2} {:7.2}", t.name,
+                100.0 * t.wins as f64 / runs, t.kills as f64 / runs, t.placement as f64 / runs));
+        }
+        lines.push(String::new());
+        let total_deaths: u64 = self.deaths_by_round.iter().map(|(_, deaths)| deaths).sum();
+        let header = format!("{:12} {:8} {:8}", text.round_type, text.deaths, text.share);
+        lines.push(header.clone());
+        lines.push("-".repeat(header.chars().count()));
+        for (name, deaths) in self.deaths_by_round.iter() {
+            lines.push(format!("{:12} {:7.2} {:6.1}%", name, *deaths as f64 / runs,
+                100.0 * *deaths as f64 / total_deaths.max(1) as f64));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Plays `runs` games without any I/O, run `i` using seed `base_seed + i`
+pub fn run_batch(game_roster: &roster::Roster, events: &EventSet, rules: &Rules, runs: u32, base_seed: u64) -> BatchStats {
+    let mut stats = BatchStats::new(game_roster, base_seed);
+    for i in 0..runs {
+        let seed = base_seed.wrapping_add(i as u64);
+        let mut sim = Simulation::new(game_roster.clone(), events.clone(), rules.clone(), seed);
+        stats.record(&mut sim);
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn games_stopped_by_max_days_have_no_winner() {
+        let round = json!({ "title": "Day {0}", "nonfatal": [{ "msg": "{0.name} rests.", "tributes": 1 }], "fatal": [] });
+        let events = EventSet::from_value(&json!({ "bloodbath": round, "day": round, "night": round, "feast": round }))
+            .unwrap();
+        let game_roster: roster::Roster = r#"[{"name": "Anne"}, {"name": "Dan"}, {"name": "Emma"}]"#.parse().unwrap();
+        let rules = Rules{max_days:3, ..Rules::default()};
+        let batch = run_batch(&game_roster, &events, &rules, 4, 1);
+        assert_eq!(batch.capped, 4);
+        assert!(batch.tributes.iter().all(|t| t.wins == 0));
+        assert_eq!(batch.total_days, 12);
+    }
+
+    #[test]
+    fn win_rates_add_up_to_every_decided_game() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("data");
+        let game_roster = roster::Roster::load(&dir.join("roster.json")).unwrap();
+        let events = EventSet::load(&dir.join("events.json")).unwrap();
+        let batch = run_batch(&game_roster, &events, &Rules::default(), 20, 1);
+        let wins: u32 = batch.tributes.iter().map(|t| t.wins).sum();
+        assert_eq!(wins + batch.capped, 20);
+    }
+}
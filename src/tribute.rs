@@ -1,8 +1,35 @@
+use std::fmt;
+use std::str::FromStr;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use serde::ser::{Serialize, Serializer, SerializeStruct};
+use serde::Deserialize;
 
 static TRIBUTE_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
+/// Fields event templates may reference, e.g. `{0.genN}`
+///
+/// `GenN` and friends are the pronouns capitalized for the start of a
+/// sentence, and `is`, `was`, `has`, `does`, `s`, `es` and `ies` agree with
+/// the tribute's pronouns: `{0.genN} {0.is}`, `run{0.s}`, `tr{0.ies}`. The
+/// verb fields are English only.
+pub const TEMPLATE_FIELDS: &[&str] = &["name", "genN", "genA", "genG", "genS", "GenN", "GenA", "GenG", "GenS",
+    "is", "was", "has", "does", "s", "es", "ies", "district"];
+
+/// Pronoun fields that want their capitalized form at the start of a sentence
+pub const PRONOUN_FIELDS: &[&str] = &["genN", "genA", "genG", "genS"];
+
+/// Verb agreement fields, which only have English forms
+pub const VERB_FIELDS: &[&str] = &["is", "was", "has", "does", "s", "es", "ies"];
+
+/// Uppercases the first letter, for pronouns that start a sentence
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Gender {
     M,
@@ -18,7 +45,12 @@ impl Gender {
             Gender::A => "A",
         }
     }
-    pub fn from_str(s: &str) -> Result<Gender, ()> {
+}
+
+impl FromStr for Gender {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Gender, ()> {
         match s {
             "M" => Ok(Gender::M),
             "F" => Ok(Gender::F),
@@ -28,6 +60,215 @@ impl Gender {
     }
 }
 
+/// Status names as written in event packs
+pub const STATUS_NAMES: &[&str] = &["wounded", "poisoned", "starving", "sick", "hidden"];
+
+/// A lasting condition that actions apply and cure
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Wounded,
+    Poisoned,
+    Starving,
+    Sick,
+    /// lasts until the next day begins
+    Hidden,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match &self {
+            Status::Wounded => "wounded",
+            Status::Poisoned => "poisoned",
+            Status::Starving => "starving",
+            Status::Sick => "sick",
+            Status::Hidden => "hidden",
+        }
+    }
+    /// How much likelier a tribute with this status is to be picked as the
+    /// victim of a fatal action
+    pub fn victim_weight(&self) -> f64 {
+        match &self {
+            Status::Wounded | Status::Poisoned => 2.0,
+            Status::Starving | Status::Sick => 1.5,
+            Status::Hidden => 0.25,
+        }
+    }
+    /// Chance of dying off-screen from this status, rolled once per day
+    pub fn daily_death_chance(&self) -> f64 {
+        match &self {
+            Status::Wounded => 0.05,
+            Status::Poisoned => 0.2,
+            Status::Starving | Status::Sick => 0.1,
+            Status::Hidden => 0.0,
+        }
+    }
+    /// Template reported in the FALLEN round for an off-screen death
+    pub fn death_msg(&self) -> &'static str {
+        match &self {
+            Status::Wounded => "{0.name} succumbs to {0.genG} wounds.",
+            Status::Poisoned => "{0.name} succumbs to poison.",
+            Status::Starving => "{0.name} starves to death.",
+            Status::Sick => "{0.name} dies of {0.genG} illness.",
+            Status::Hidden => "{0.name} dies in hiding.",
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Status, ()> {
+        match s {
+            "wounded" => Ok(Status::Wounded),
+            "poisoned" => Ok(Status::Poisoned),
+            "starving" => Ok(Status::Starving),
+            "sick" => Ok(Status::Sick),
+            "hidden" => Ok(Status::Hidden),
+            _ => Err(()),
+        }
+    }
+}
+
+/// An explicit pronoun set, overriding the defaults for a tribute's gender
+#[derive(Clone, Debug, PartialEq, Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pronouns {
+    pub nominative: String,
+    pub accusative: String,
+    pub genitive: String,
+    pub reflexive: String,
+    /// takes plural verbs, as in "they are"
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub plural: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+impl Pronouns {
+    fn new(nominative: &str, accusative: &str, genitive: &str, reflexive: &str, plural: bool) -> Pronouns {
+        Pronouns{nominative:nominative.to_string(), accusative:accusative.to_string(),
+            genitive:genitive.to_string(), reflexive:reflexive.to_string(), plural}
+    }
+    /// The set used for a gender when a tribute has none of its own
+    pub fn for_gender(gender: &Gender) -> Pronouns {
+        match gender {
+            Gender::M => Pronouns::new("he", "him", "his", "himself", false),
+            Gender::F => Pronouns::new("she", "her", "her", "herself", false),
+            Gender::A => Pronouns::new("they", "them", "their", "themselves", true),
+        }
+    }
+    /// The `xe/xem/xyr/xemself` shorthand, with an optional `/plural` on the end
+    pub fn as_short(&self) -> String {
+        let short = format!("{}/{}/{}/{}", self.nominative, self.accusative, self.genitive, self.reflexive);
+        if self.plural { format!("{}/plural", short) } else { short }
+    }
+}
+
+impl FromStr for Pronouns {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Pronouns, ()> {
+        let forms: Vec<&str> = s.split('/').map(str::trim).collect();
+        if forms.iter().any(|f| f.is_empty()) {
+            return Err(());
+        }
+        match forms[..] {
+            [n, a, g, r] => Ok(Pronouns::new(n, a, g, r, false)),
+            [n, a, g, r, "plural"] => Ok(Pronouns::new(n, a, g, r, true)),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Attribute scores, each from `STAT_MIN` to `STAT_MAX`
+#[derive(Clone, Debug, PartialEq, Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Stats {
+    pub strength: i32,
+    pub agility: i32,
+    pub wits: i32,
+    pub luck: i32,
+}
+
+pub const STAT_MIN: i32 = 1;
+pub const STAT_MAX: i32 = 10;
+
+/// The score every stat defaults to, and the one that leaves odds unchanged
+const STAT_AVERAGE: f64 = 5.0;
+
+impl Default for Stats {
+    fn default() -> Stats {
+        Stats{strength:5, agility:5, wits:5, luck:5}
+    }
+}
+
+/// Stat names as written in event packs
+pub const STAT_NAMES: &[&str] = &["strength", "agility", "wits", "luck"];
+
+/// One of the stats, for actions that are decided by it
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stat {
+    Strength,
+    Agility,
+    Wits,
+    Luck,
+}
+
+impl Stats {
+    pub fn get(&self, stat: Stat) -> i32 {
+        match stat {
+            Stat::Strength => self.strength,
+            Stat::Agility => self.agility,
+            Stat::Wits => self.wits,
+            Stat::Luck => self.luck,
+        }
+    }
+    /// The score an action is decided by: the named stat, or the mean of
+    /// strength, agility and wits
+    pub fn power(&self, stat: Option<Stat>) -> f64 {
+        match stat {
+            Some(stat) => self.get(stat) as f64,
+            None => (self.strength + self.agility + self.wits) as f64 / 3.0,
+        }
+    }
+    /// Pick weight for the winning side of an action
+    pub fn winner_weight(&self, stat: Option<Stat>) -> f64 {
+        self.power(stat) / STAT_AVERAGE
+    }
+    /// Pick weight for the losing side; luck helps a little on top
+    pub fn loser_weight(&self, stat: Option<Stat>) -> f64 {
+        STAT_AVERAGE / self.power(stat) * (STAT_AVERAGE / self.luck as f64).sqrt()
+    }
+}
+
+/// One tribute as written in a roster file; only `name` is required
+#[derive(Clone, Debug, Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TributeEntry {
+    pub name: String,
+    #[serde(default = "default_gender")]
+    pub gender: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pronouns: Option<Pronouns>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub district: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<Stats>,
+    /// item tags carried into the arena
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inventory: Vec<String>,
+}
+
+fn default_gender() -> String {
+    String::from("A")
+}
+
 #[derive(Clone, Debug)]
 pub struct Tribute {
     pub name: String,
@@ -41,7 +282,15 @@ pub struct Tribute {
     pub gender_label_accusative: String,
     pub gender_label_genitive: String,
     pub gender_label_reflexitive: String,
-    pub avatar: Option<String>
+    /// takes plural verbs, from the pronoun set
+    pub plural_verbs: bool,
+    pub avatar: Option<String>,
+    pub pronouns: Option<Pronouns>,
+    pub district: Option<u32>,
+    pub stats: Stats,
+    /// item tags, one entry per item carried
+    pub inventory: Vec<String>,
+    pub statuses: Vec<Status>,
 }
 
 impl Serialize for Tribute {
@@ -56,6 +305,19 @@ impl Serialize for Tribute {
         state.serialize_field("genA", &self.gender_label_accusative.as_str())?;
         state.serialize_field("genG", &self.gender_label_genitive.as_str())?;
         state.serialize_field("genS", &self.gender_label_reflexitive.as_str())?;
+        state.serialize_field("GenN", &capitalize(&self.gender_label_nominative))?;
+        state.serialize_field("GenA", &capitalize(&self.gender_label_accusative))?;
+        state.serialize_field("GenG", &capitalize(&self.gender_label_genitive))?;
+        state.serialize_field("GenS", &capitalize(&self.gender_label_reflexitive))?;
+        let plural = self.plural_verbs;
+        state.serialize_field("is", if plural { "are" } else { "is" })?;
+        state.serialize_field("was", if plural { "were" } else { "was" })?;
+        state.serialize_field("has", if plural { "have" } else { "has" })?;
+        state.serialize_field("does", if plural { "do" } else { "does" })?;
+        state.serialize_field("s", if plural { "" } else { "s" })?;
+        state.serialize_field("es", if plural { "" } else { "es" })?;
+        state.serialize_field("ies", if plural { "y" } else { "ies" })?;
+        state.serialize_field("district", &self.district)?;
         state.skip_field("id")?;
         state.skip_field("alive")?;
         state.skip_field("available")?;
@@ -65,24 +327,86 @@ impl Serialize for Tribute {
     }
 }
 
+impl Default for Tribute {
+    fn default() -> Tribute {
+        Tribute::new()
+    }
+}
+
 impl Tribute {
     pub fn new() -> Tribute {
         let tribute_id = TRIBUTE_COUNTER.fetch_add(1, Ordering::SeqCst);
         Tribute{name:String::from("null"), id:tribute_id, alive:false, available:false, deathday:0, killcount:0, gender:Gender::A,
             gender_label_nominative:String::from("null"), gender_label_accusative:String::from("null"), 
-            gender_label_genitive:String::from("null"), gender_label_reflexitive:String::from("null"), avatar:None}
+            gender_label_genitive:String::from("null"), gender_label_reflexitive:String::from("null"), plural_verbs:false, avatar:None,
+            pronouns:None, district:None, stats:Stats::default(), inventory:Vec::new(), statuses:Vec::new()}
     }
     pub fn from_data(name: &str, gen: &str, avatar: &str) -> Tribute {
         let tribute_id = TRIBUTE_COUNTER.fetch_add(1, Ordering::SeqCst);
-        let gen_result = match Gender::from_str(gen) {
+        let gen_result = match gen.parse::<Gender>() {
             Ok(v) => v,
             Err(_) => Gender::A
         };
         Tribute{name:String::from(name), id:tribute_id, alive:true, available:true, deathday:0, killcount:0, gender:gen_result,
             gender_label_nominative:String::from("null"), gender_label_accusative:String::from("null"), 
-            gender_label_genitive:String::from("null"), gender_label_reflexitive:String::from("null"), avatar:Some(avatar.to_string())}
+            gender_label_genitive:String::from("null"), gender_label_reflexitive:String::from("null"), plural_verbs:false, avatar:Some(avatar.to_string()),
+            pronouns:None, district:None, stats:Stats::default(), inventory:Vec::new(), statuses:Vec::new()}
+    }
+    pub fn from_entry(entry: &TributeEntry) -> Tribute {
+        let mut tb = Tribute::from_data(&entry.name, &entry.gender, "");
+        tb.avatar = entry.avatar.clone();
+        tb.pronouns = entry.pronouns.clone();
+        tb.district = entry.district;
+        tb.stats = entry.stats.clone().unwrap_or_default();
+        tb.inventory = entry.inventory.clone();
+        tb
+    }
+    /// The roster file form of this tribute, leaving out default values
+    pub fn to_entry(&self) -> TributeEntry {
+        TributeEntry{name:self.name.clone(), gender:self.gender.as_str().to_string(),
+            avatar:self.avatar.clone(), pronouns:self.pronouns.clone(), district:self.district,
+            stats:if self.stats == Stats::default() { None } else { Some(self.stats.clone()) },
+            inventory:self.inventory.clone()}
     }
-    pub fn to_string(self) -> String {
-        format!("Name: {}, ID: {}", self.name, self.id)
+    /// True when the inventory holds every tag, counting repeats
+    pub fn has_items(&self, tags: &[&str]) -> bool {
+        tags.iter().all(|tag| {
+            let wanted = tags.iter().filter(|t| *t == tag).count();
+            self.inventory.iter().filter(|item| item == tag).count() >= wanted
+        })
+    }
+    pub fn give_item(&mut self, tag: &str) {
+        self.inventory.push(tag.to_string());
+    }
+    pub fn has_status(&self, status: Status) -> bool {
+        self.statuses.contains(&status)
+    }
+    pub fn add_status(&mut self, status: Status) {
+        if !self.has_status(status) {
+            self.statuses.push(status);
+        }
+    }
+    pub fn cure_status(&mut self, status: Status) {
+        self.statuses.retain(|&s| s != status);
+    }
+    /// Removes one item with this tag, returning false if there was none
+    pub fn take_item(&mut self, tag: &str) -> bool {
+        match self.inventory.iter().position(|item| item == tag) {
+            Some(pos) => {
+                self.inventory.remove(pos);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for Tribute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Name: {}, ID: {}", self.name, self.id)?;
+        if let Some(d) = self.district {
+            write!(f, ", District: {}", d)?;
+        }
+        Ok(())
     }
 }
\ No newline at end of file